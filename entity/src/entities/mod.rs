@@ -2,5 +2,19 @@
 
 pub mod prelude;
 
+pub mod api_keys;
+pub mod attachments;
+pub mod comments;
+pub mod lists;
 pub mod migrations;
+pub mod oidc_logins;
+pub mod refresh_tokens;
+pub mod sea_orm_active_enums;
+pub mod shares;
+pub mod tags;
+pub mod todo_events;
+pub mod todo_tags;
 pub mod todos;
+pub mod users;
+pub mod workspace_members;
+pub mod workspaces;
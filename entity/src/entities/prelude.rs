@@ -1,4 +1,17 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+pub use super::api_keys::Entity as ApiKeys;
+pub use super::attachments::Entity as Attachments;
+pub use super::comments::Entity as Comments;
+pub use super::lists::Entity as Lists;
 pub use super::migrations::Entity as Migrations;
+pub use super::oidc_logins::Entity as OidcLogins;
+pub use super::refresh_tokens::Entity as RefreshTokens;
+pub use super::shares::Entity as Shares;
+pub use super::tags::Entity as Tags;
+pub use super::todo_events::Entity as TodoEvents;
+pub use super::todo_tags::Entity as TodoTags;
 pub use super::todos::Entity as Todos;
+pub use super::users::Entity as Users;
+pub use super::workspace_members::Entity as WorkspaceMembers;
+pub use super::workspaces::Entity as Workspaces;
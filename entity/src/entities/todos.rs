@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+use super::sea_orm_active_enums::Priority;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -13,9 +14,114 @@ pub struct Model {
     pub complated: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    pub version: i32,
+    pub deleted_at: Option<DateTime>,
+    pub due_at: Option<DateTime>,
+    pub priority: Priority,
+    pub completed_at: Option<DateTime>,
+    pub list_id: Option<i64>,
+    pub parent_id: Option<i64>,
+    pub rank: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub recurrence: Option<String>,
+    pub recurrence_start: Option<DateTime>,
+    pub owner_id: Option<i64>,
+    pub workspace_id: i64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::attachments::Entity")]
+    Attachments,
+    #[sea_orm(has_many = "super::comments::Entity")]
+    Comments,
+    #[sea_orm(
+        belongs_to = "super::lists::Entity",
+        from = "Column::ListId",
+        to = "super::lists::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Lists,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::shares::Entity")]
+    Shares,
+    #[sea_orm(has_many = "super::todo_tags::Entity")]
+    TodoTags,
+    #[sea_orm(
+        belongs_to = "super::users::Entity",
+        from = "Column::OwnerId",
+        to = "super::users::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Users,
+    #[sea_orm(
+        belongs_to = "super::workspaces::Entity",
+        from = "Column::WorkspaceId",
+        to = "super::workspaces::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Workspaces,
+}
+
+impl Related<super::attachments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Attachments.def()
+    }
+}
+
+impl Related<super::comments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comments.def()
+    }
+}
+
+impl Related<super::lists::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Lists.def()
+    }
+}
+
+impl Related<super::shares::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shares.def()
+    }
+}
+
+impl Related<super::todo_tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TodoTags.def()
+    }
+}
+
+impl Related<super::tags::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::todo_tags::Relation::Tags.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::todo_tags::Relation::Todos.def().rev())
+    }
+}
+
+impl Related<super::users::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Users.def()
+    }
+}
+
+impl Related<super::workspaces::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Workspaces.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,6 +1,32 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20240118_000001_create_table;
+mod m20240301_000001_create_todos_created_at_index;
+mod m20240305_000001_add_todos_body_search;
+mod m20240310_000001_add_todos_version;
+mod m20240315_000001_add_todos_deleted_at;
+mod m20240320_000001_add_todos_planning_fields;
+mod m20240325_000001_create_tags;
+mod m20240330_000001_create_lists;
+mod m20240405_000001_add_todos_parent_id;
+mod m20240410_000001_add_todos_rank;
+mod m20240415_000001_add_todos_recurrence;
+mod m20240420_000001_create_todo_events;
+mod m20240425_000001_add_reverted_event_kind;
+mod m20240430_000001_create_comments;
+mod m20240505_000001_create_attachments;
+mod m20240510_000001_create_users;
+mod m20240515_000001_add_todos_owner_id;
+mod m20240520_000001_create_refresh_tokens;
+mod m20240525_000001_create_api_keys;
+mod m20240530_000001_add_oidc_logins;
+mod m20240604_000001_create_shares;
+mod m20240610_000001_create_workspaces;
+mod m20240615_000001_add_workspace_row_level_security;
+mod m20240620_000001_add_lists_owner_id;
+mod m20240625_000001_add_comments_author_id;
+mod m20240630_000001_add_comments_attachments_workspace_id;
+mod m20240705_000001_deny_rows_outside_workspace;
 
 pub struct Migrator;
 
@@ -11,6 +37,34 @@ impl MigratorTrait for Migrator {
     }
 
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240118_000001_create_table::Migration)]
+        vec![
+            Box::new(m20240118_000001_create_table::Migration),
+            Box::new(m20240301_000001_create_todos_created_at_index::Migration),
+            Box::new(m20240305_000001_add_todos_body_search::Migration),
+            Box::new(m20240310_000001_add_todos_version::Migration),
+            Box::new(m20240315_000001_add_todos_deleted_at::Migration),
+            Box::new(m20240320_000001_add_todos_planning_fields::Migration),
+            Box::new(m20240325_000001_create_tags::Migration),
+            Box::new(m20240330_000001_create_lists::Migration),
+            Box::new(m20240405_000001_add_todos_parent_id::Migration),
+            Box::new(m20240410_000001_add_todos_rank::Migration),
+            Box::new(m20240415_000001_add_todos_recurrence::Migration),
+            Box::new(m20240420_000001_create_todo_events::Migration),
+            Box::new(m20240425_000001_add_reverted_event_kind::Migration),
+            Box::new(m20240430_000001_create_comments::Migration),
+            Box::new(m20240505_000001_create_attachments::Migration),
+            Box::new(m20240510_000001_create_users::Migration),
+            Box::new(m20240515_000001_add_todos_owner_id::Migration),
+            Box::new(m20240520_000001_create_refresh_tokens::Migration),
+            Box::new(m20240525_000001_create_api_keys::Migration),
+            Box::new(m20240530_000001_add_oidc_logins::Migration),
+            Box::new(m20240604_000001_create_shares::Migration),
+            Box::new(m20240610_000001_create_workspaces::Migration),
+            Box::new(m20240615_000001_add_workspace_row_level_security::Migration),
+            Box::new(m20240620_000001_add_lists_owner_id::Migration),
+            Box::new(m20240625_000001_add_comments_author_id::Migration),
+            Box::new(m20240630_000001_add_comments_attachments_workspace_id::Migration),
+            Box::new(m20240705_000001_deny_rows_outside_workspace::Migration),
+        ]
     }
 }
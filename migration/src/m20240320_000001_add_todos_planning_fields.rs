@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // declaration order is the sort order of a postgres enum
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(Priority::Enum)
+                    .values([
+                        Priority::Low,
+                        Priority::Normal,
+                        Priority::High,
+                        Priority::Urgent,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Todos::DueAt).timestamp().null())
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todos::Priority)
+                            .enumeration(
+                                Priority::Enum,
+                                [
+                                    Priority::Low,
+                                    Priority::Normal,
+                                    Priority::High,
+                                    Priority::Urgent,
+                                ],
+                            )
+                            .not_null()
+                            .default("normal"),
+                    )
+                    .add_column_if_not_exists(ColumnDef::new(Todos::CompletedAt).timestamp().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // todos completed before the column existed count as completed at their last update
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Todos::Table)
+                    .value(Todos::CompletedAt, Expr::col(Todos::UpdatedAt))
+                    .and_where(Expr::col(Todos::Complated).eq(true))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .drop_column(Todos::DueAt)
+                    .drop_column(Todos::Priority)
+                    .drop_column(Todos::CompletedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(Priority::Enum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    Complated,
+    UpdatedAt,
+    DueAt,
+    Priority,
+    CompletedAt,
+}
+
+#[derive(DeriveIden)]
+enum Priority {
+    #[sea_orm(iden = "priority")]
+    Enum,
+    Low,
+    Normal,
+    High,
+    Urgent,
+}
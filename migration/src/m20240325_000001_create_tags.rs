@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Tags::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Tags::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Tags::Name).string().not_null().unique_key())
+                    .col(
+                        ColumnDef::new(Tags::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .col(
+                        ColumnDef::new(Tags::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TodoTags::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(TodoTags::TodoId).big_integer().not_null())
+                    .col(ColumnDef::new(TodoTags::TagId).big_integer().not_null())
+                    .primary_key(Index::create().col(TodoTags::TodoId).col(TodoTags::TagId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_todo_tags_todo_id")
+                            .from(TodoTags::Table, TodoTags::TodoId)
+                            .to(Todos::Table, Todos::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_todo_tags_tag_id")
+                            .from(TodoTags::Table, TodoTags::TagId)
+                            .to(Tags::Table, Tags::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the primary key covers lookups by todo, filtering by tag needs its own index
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_todo_tags_tag_id")
+                    .table(TodoTags::Table)
+                    .col(TodoTags::TagId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TodoTags::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Tags::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Tags {
+    Table,
+    Id,
+    Name,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TodoTags {
+    Table,
+    TodoId,
+    TagId,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // ranks are compared byte by byte, whatever the default collation of the database is
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(Todos::Rank)
+                            .string()
+                            .not_null()
+                            .default("")
+                            .extra("COLLATE \"C\""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // existing todos keep their creation order, hex digits are valid rank digits and
+        // trimming trailing zeros keeps the order of fixed width numbers
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"
+            UPDATE todos SET rank = ranked.rank
+            FROM (
+                SELECT id, rtrim(lpad(to_hex(row_number() OVER (ORDER BY created_at, id)), 12, '0'), '0') AS rank
+                FROM todos
+            ) AS ranked
+            WHERE todos.id = ranked.id
+            "#,
+        )
+        .await?;
+        db.execute_unprepared("ALTER TABLE todos ALTER COLUMN rank DROP DEFAULT")
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_todos_rank_id")
+                    .table(Todos::Table)
+                    .col(Todos::Rank)
+                    .col(Todos::Id)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .drop_column(Todos::Rank)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    Id,
+    Rank,
+}
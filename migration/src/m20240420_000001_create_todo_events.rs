@@ -0,0 +1,121 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(TodoEventKind::Enum)
+                    .values([
+                        TodoEventKind::Created,
+                        TodoEventKind::Updated,
+                        TodoEventKind::Trashed,
+                        TodoEventKind::Restored,
+                        TodoEventKind::Purged,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        // no foreign key to todos, the history of a purged todo is kept
+        manager
+            .create_table(
+                Table::create()
+                    .table(TodoEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TodoEvents::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TodoEvents::TodoId).big_integer().not_null())
+                    .col(ColumnDef::new(TodoEvents::Version).integer().not_null())
+                    .col(
+                        ColumnDef::new(TodoEvents::Kind)
+                            .enumeration(
+                                TodoEventKind::Enum,
+                                [
+                                    TodoEventKind::Created,
+                                    TodoEventKind::Updated,
+                                    TodoEventKind::Trashed,
+                                    TodoEventKind::Restored,
+                                    TodoEventKind::Purged,
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TodoEvents::Actor).text().null())
+                    .col(ColumnDef::new(TodoEvents::RequestId).text().null())
+                    .col(ColumnDef::new(TodoEvents::Before).json_binary().null())
+                    .col(ColumnDef::new(TodoEvents::After).json_binary().null())
+                    .col(ColumnDef::new(TodoEvents::Diff).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(TodoEvents::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_todo_events_todo_id_id")
+                    .table(TodoEvents::Table)
+                    .col(TodoEvents::TodoId)
+                    .col(TodoEvents::Id)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TodoEvents::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(TodoEventKind::Enum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TodoEvents {
+    Table,
+    Id,
+    TodoId,
+    Version,
+    Kind,
+    Actor,
+    RequestId,
+    Before,
+    After,
+    Diff,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum TodoEventKind {
+    #[sea_orm(iden = "todo_event_kind")]
+    Enum,
+    Created,
+    Updated,
+    Trashed,
+    Restored,
+    Purged,
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_type(
+                Type::alter()
+                    .name(TodoEventKind::Enum)
+                    .add_value(TodoEventKind::Reverted),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // postgres cannot drop a value from an enum, an unused value is harmless
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TodoEventKind {
+    #[sea_orm(iden = "todo_event_kind")]
+    Enum,
+    Reverted,
+}
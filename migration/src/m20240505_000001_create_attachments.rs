@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // only the metadata lives here, the contents are kept in the blob store under storage_key
+        manager
+            .create_table(
+                Table::create()
+                    .table(Attachments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Attachments::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Attachments::TodoId).big_integer().not_null())
+                    .col(ColumnDef::new(Attachments::FileName).text().not_null())
+                    .col(ColumnDef::new(Attachments::ContentType).string().not_null())
+                    .col(ColumnDef::new(Attachments::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(Attachments::Sha256)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Attachments::StorageKey)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Attachments::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_attachments_todo_id")
+                            .from(Attachments::Table, Attachments::TodoId)
+                            .to(Todos::Table, Todos::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_attachments_todo_id_id")
+                    .table(Attachments::Table)
+                    .col(Attachments::TodoId)
+                    .col(Attachments::Id)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Attachments::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Attachments {
+    Table,
+    Id,
+    TodoId,
+    FileName,
+    ContentType,
+    Size,
+    Sha256,
+    StorageKey,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    Id,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // todos created before users existed have no owner and are out of everyone's reach
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Todos::OwnerId).big_integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_todos_owner_id")
+                            .from_tbl(Todos::Table)
+                            .from_col(Todos::OwnerId)
+                            .to_tbl(Users::Table)
+                            .to_col(Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_todos_owner_id")
+                    .table(Todos::Table)
+                    .col(Todos::OwnerId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // the history outlives purged todos, so it carries the owner itself
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TodoEvents::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(TodoEvents::OwnerId).big_integer().null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TodoEvents::Table)
+                    .drop_column(TodoEvents::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Todos::Table)
+                    .drop_column(Todos::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    OwnerId,
+}
+
+#[derive(DeriveIden)]
+enum TodoEvents {
+    Table,
+    OwnerId,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,123 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ApiKeyScope::Enum)
+                    .values([ApiKeyScope::Read, ApiKeyScope::ReadWrite])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ApiKeys::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ApiKeys::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ApiKeys::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(ApiKeys::Name).string().not_null())
+                    // shown to tell keys apart and used to find the key, the rest is only hashed
+                    .col(
+                        ColumnDef::new(ApiKeys::Prefix)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(ApiKeys::Salt).string().not_null())
+                    .col(ColumnDef::new(ApiKeys::KeyHash).string().not_null())
+                    .col(
+                        ColumnDef::new(ApiKeys::Scope)
+                            .enumeration(
+                                ApiKeyScope::Enum,
+                                [ApiKeyScope::Read, ApiKeyScope::ReadWrite],
+                            )
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ApiKeys::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .col(ColumnDef::new(ApiKeys::ExpiresAt).timestamp().null())
+                    .col(ColumnDef::new(ApiKeys::LastUsedAt).timestamp().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_api_keys_user_id")
+                            .from(ApiKeys::Table, ApiKeys::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_api_keys_user_id")
+                    .table(ApiKeys::Table)
+                    .col(ApiKeys::UserId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ApiKeys::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(ApiKeyScope::Enum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum ApiKeys {
+    Table,
+    Id,
+    UserId,
+    Name,
+    Prefix,
+    Salt,
+    KeyHash,
+    Scope,
+    CreatedAt,
+    ExpiresAt,
+    LastUsedAt,
+}
+
+#[derive(DeriveIden)]
+enum ApiKeyScope {
+    #[sea_orm(iden = "api_key_scope")]
+    Enum,
+    Read,
+    ReadWrite,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,125 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // users signing in through the identity provider have no password
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::PasswordHash).text().null())
+                    .add_column_if_not_exists(ColumnDef::new(Users::OidcIssuer).string().null())
+                    .add_column_if_not_exists(ColumnDef::new(Users::OidcSubject).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_users_oidc_identity")
+                    .table(Users::Table)
+                    .col(Users::OidcIssuer)
+                    .col(Users::OidcSubject)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // logins started at the provider, each one can be completed once
+        manager
+            .create_table(
+                Table::create()
+                    .table(OidcLogins::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OidcLogins::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OidcLogins::State)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(OidcLogins::CodeVerifier).string().not_null())
+                    .col(ColumnDef::new(OidcLogins::Nonce).string().not_null())
+                    .col(
+                        ColumnDef::new(OidcLogins::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .col(ColumnDef::new(OidcLogins::ExpiresAt).timestamp().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OidcLogins::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_users_oidc_identity")
+                    .table(Users::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // accounts without a password cannot be kept
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Users::Table)
+                    .and_where(Expr::col(Users::PasswordHash).is_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .modify_column(ColumnDef::new(Users::PasswordHash).text().not_null())
+                    .drop_column(Users::OidcIssuer)
+                    .drop_column(Users::OidcSubject)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    PasswordHash,
+    OidcIssuer,
+    OidcSubject,
+}
+
+#[derive(DeriveIden)]
+enum OidcLogins {
+    Table,
+    Id,
+    State,
+    CodeVerifier,
+    Nonce,
+    CreatedAt,
+    ExpiresAt,
+}
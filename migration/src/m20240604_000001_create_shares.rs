@@ -0,0 +1,176 @@
+use sea_orm_migration::prelude::*;
+use sea_orm_migration::sea_query::extension::postgres::Type;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // declaration order is the order of the rights they grant, the owner is never stored
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(ShareRole::Enum)
+                    .values([ShareRole::Viewer, ShareRole::Editor])
+                    .to_owned(),
+            )
+            .await?;
+
+        // a share grants a user a role on one todo, or on every todo of the owner in a list
+        manager
+            .create_table(
+                Table::create()
+                    .table(Shares::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Shares::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Shares::OwnerId).big_integer().not_null())
+                    .col(ColumnDef::new(Shares::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(Shares::TodoId).big_integer().null())
+                    .col(ColumnDef::new(Shares::ListId).big_integer().null())
+                    .col(
+                        ColumnDef::new(Shares::Role)
+                            .enumeration(ShareRole::Enum, [ShareRole::Viewer, ShareRole::Editor])
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Shares::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    // exactly one of todo and list is shared
+                    .check(
+                        Expr::expr(Expr::col(Shares::TodoId).is_null())
+                            .ne(Expr::col(Shares::ListId).is_null()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_shares_owner_id")
+                            .from(Shares::Table, Shares::OwnerId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_shares_user_id")
+                            .from(Shares::Table, Shares::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_shares_todo_id")
+                            .from(Shares::Table, Shares::TodoId)
+                            .to(Todos::Table, Todos::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_shares_list_id")
+                            .from(Shares::Table, Shares::ListId)
+                            .to(Lists::Table, Lists::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // nulls are distinct, so each index only constrains shares of its kind
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_shares_user_id_todo_id")
+                    .table(Shares::Table)
+                    .col(Shares::UserId)
+                    .col(Shares::TodoId)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_shares_user_id_list_id_owner_id")
+                    .table(Shares::Table)
+                    .col(Shares::UserId)
+                    .col(Shares::ListId)
+                    .col(Shares::OwnerId)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_shares_owner_id")
+                    .table(Shares::Table)
+                    .col(Shares::OwnerId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Shares::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(ShareRole::Enum).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Shares {
+    Table,
+    Id,
+    OwnerId,
+    UserId,
+    TodoId,
+    ListId,
+    Role,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum ShareRole {
+    #[sea_orm(iden = "share_role")]
+    Enum,
+    Viewer,
+    Editor,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Todos {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Lists {
+    Table,
+    Id,
+}
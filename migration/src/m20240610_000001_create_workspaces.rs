@@ -0,0 +1,284 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables holding the data of a workspace, everything else hangs off a todo
+const SCOPED_TABLES: [&str; 5] = ["todos", "lists", "tags", "shares", "todo_events"];
+
+// Credentials that can be limited to one workspace
+const PINNED_TABLES: [&str; 2] = ["api_keys", "refresh_tokens"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Workspaces::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Workspaces::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Workspaces::Slug)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Workspaces::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Workspaces::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // admins manage the members, everyone else only works in the workspace
+        manager
+            .create_table(
+                Table::create()
+                    .table(WorkspaceMembers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WorkspaceMembers::WorkspaceId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkspaceMembers::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WorkspaceMembers::Admin)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(WorkspaceMembers::CreatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(SimpleExpr::Keyword(Keyword::CurrentTimestamp)),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(WorkspaceMembers::WorkspaceId)
+                            .col(WorkspaceMembers::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_members_workspace_id")
+                            .from(WorkspaceMembers::Table, WorkspaceMembers::WorkspaceId)
+                            .to(Workspaces::Table, Workspaces::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_workspace_members_user_id")
+                            .from(WorkspaceMembers::Table, WorkspaceMembers::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the primary key covers lookups by workspace, listing the workspaces of a user needs its own
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_workspace_members_user_id")
+                    .table(WorkspaceMembers::Table)
+                    .col(WorkspaceMembers::UserId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // everything so far moves to the workspace requests without one are made in, the slug
+        // matches the default of `tenancy.default_workspace`
+        let db = manager.get_connection();
+        db.execute_unprepared("INSERT INTO workspaces (slug, name) VALUES ('default', 'Default')")
+            .await?;
+        db.execute_unprepared(
+            r#"
+            INSERT INTO workspace_members (workspace_id, user_id)
+            SELECT workspaces.id, users.id FROM workspaces CROSS JOIN users
+            WHERE workspaces.slug = 'default'
+            "#,
+        )
+        .await?;
+
+        for name in SCOPED_TABLES {
+            let table = Alias::new(name);
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Scoped::WorkspaceId).big_integer().null(),
+                        )
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name(format!("fk_{name}_workspace_id"))
+                                .from_tbl(table.clone())
+                                .from_col(Scoped::WorkspaceId)
+                                .to_tbl(Workspaces::Table)
+                                .to_col(Workspaces::Id)
+                                .on_delete(ForeignKeyAction::Cascade),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            db.execute_unprepared(&format!(
+                "UPDATE {name} SET workspace_id = (SELECT id FROM workspaces WHERE slug = 'default');
+                 ALTER TABLE {name} ALTER COLUMN workspace_id SET NOT NULL"
+            ))
+            .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx_{name}_workspace_id"))
+                        .table(table)
+                        .col(Scoped::WorkspaceId)
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // tag names are only unique within a workspace
+        db.execute_unprepared("ALTER TABLE tags DROP CONSTRAINT tags_name_key")
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tags_workspace_id_name")
+                    .table(Tags::Table)
+                    .col(Scoped::WorkspaceId)
+                    .col(Tags::Name)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // credentials limited to one workspace, the ones issued so far work in every workspace
+        // of their user
+        for name in PINNED_TABLES {
+            let table = Alias::new(name);
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Scoped::WorkspaceId).big_integer().null(),
+                        )
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name(format!("fk_{name}_workspace_id"))
+                                .from_tbl(table)
+                                .from_col(Scoped::WorkspaceId)
+                                .to_tbl(Workspaces::Table)
+                                .to_col(Workspaces::Id)
+                                .on_delete(ForeignKeyAction::Cascade),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in PINNED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Scoped::WorkspaceId)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_tags_workspace_id_name")
+                    .table(Tags::Table)
+                    .to_owned(),
+            )
+            .await?;
+        // fails if the same tag name is used in several workspaces by now
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE tags ADD CONSTRAINT tags_name_key UNIQUE (name)")
+            .await?;
+
+        for table in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Scoped::WorkspaceId)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(WorkspaceMembers::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Workspaces::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workspaces {
+    Table,
+    Id,
+    Slug,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum WorkspaceMembers {
+    Table,
+    WorkspaceId,
+    UserId,
+    Admin,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Scoped {
+    WorkspaceId,
+}
+
+#[derive(DeriveIden)]
+enum Tags {
+    Table,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
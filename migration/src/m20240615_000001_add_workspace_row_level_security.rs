@@ -0,0 +1,51 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Same as the tables given a workspace when workspaces were created
+const SCOPED_TABLES: [&str; 5] = ["todos", "lists", "tags", "shares", "todo_events"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Rows are limited to the workspace in `olivier.workspace_id`, which the service only sets
+        // inside its transactions with `tenancy.row_level_security` on. Without it set, or left
+        // empty on a pooled connection once such a transaction ended, every row passes, so turning
+        // the setting off needs no migration back. Forced so the owner of the tables is bound
+        // too, superusers and roles with BYPASSRLS never are.
+        let db = manager.get_connection();
+        for name in SCOPED_TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                ALTER TABLE {name} ENABLE ROW LEVEL SECURITY;
+                ALTER TABLE {name} FORCE ROW LEVEL SECURITY;
+                CREATE POLICY workspace_isolation ON {name}
+                USING (
+                    nullif(current_setting('olivier.workspace_id', true), '') IS NULL
+                    OR workspace_id = nullif(current_setting('olivier.workspace_id', true), '')::bigint
+                )
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for name in SCOPED_TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                DROP POLICY IF EXISTS workspace_isolation ON {name};
+                ALTER TABLE {name} NO FORCE ROW LEVEL SECURITY;
+                ALTER TABLE {name} DISABLE ROW LEVEL SECURITY;
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // lists created before they had an owner are left to the admins of their workspace
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lists::Table)
+                    .add_column_if_not_exists(ColumnDef::new(Lists::OwnerId).big_integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_lists_owner_id")
+                            .from_tbl(Lists::Table)
+                            .from_col(Lists::OwnerId)
+                            .to_tbl(Users::Table)
+                            .to_col(Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_lists_owner_id")
+                    .table(Lists::Table)
+                    .col(Lists::OwnerId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Lists::Table)
+                    .drop_column(Lists::OwnerId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Lists {
+    Table,
+    OwnerId,
+}
+
+#[derive(DeriveIden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables hanging off a todo that are kept to a workspace by row level security as well
+const SCOPED_TABLES: [&str; 2] = ["comments", "attachments"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for name in SCOPED_TABLES {
+            let table = Alias::new(name);
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table.clone())
+                        .add_column_if_not_exists(
+                            ColumnDef::new(Scoped::WorkspaceId).big_integer().null(),
+                        )
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name(format!("fk_{name}_workspace_id"))
+                                .from_tbl(table.clone())
+                                .from_col(Scoped::WorkspaceId)
+                                .to_tbl(Workspaces::Table)
+                                .to_col(Workspaces::Id)
+                                .on_delete(ForeignKeyAction::Cascade),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            // rows are in the workspace of their todo
+            db.execute_unprepared(&format!(
+                "UPDATE {name} SET workspace_id = todos.workspace_id FROM todos WHERE todos.id = {name}.todo_id;
+                 ALTER TABLE {name} ALTER COLUMN workspace_id SET NOT NULL"
+            ))
+            .await?;
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx_{name}_workspace_id"))
+                        .table(table)
+                        .col(Scoped::WorkspaceId)
+                        .if_not_exists()
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in SCOPED_TABLES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Alias::new(table))
+                        .drop_column(Scoped::WorkspaceId)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Workspaces {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Scoped {
+    WorkspaceId,
+}
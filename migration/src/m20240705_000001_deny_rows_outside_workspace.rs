@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Tables kept to a workspace so far, whose policies let every row pass without one set
+const SCOPED_TABLES: [&str; 5] = ["todos", "lists", "tags", "shares", "todo_events"];
+
+// Tables given a workspace along with comments and attachments, not yet kept to it
+const NEW_SCOPED_TABLES: [&str; 2] = ["comments", "attachments"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Rows are limited to the workspace in `olivier.workspace_id`, which the service sets at
+        // the start of the transactions of a request. Without it set, or left empty on a pooled
+        // connection once such a transaction ended, no row passes. Forced so the owner of the
+        // tables is bound too, superusers and roles with BYPASSRLS never are.
+        let db = manager.get_connection();
+        for name in SCOPED_TABLES {
+            db.execute_unprepared(&format!("DROP POLICY workspace_isolation ON {name}"))
+                .await?;
+        }
+        for name in NEW_SCOPED_TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                ALTER TABLE {name} ENABLE ROW LEVEL SECURITY;
+                ALTER TABLE {name} FORCE ROW LEVEL SECURITY;
+                "#
+            ))
+            .await?;
+        }
+        for name in SCOPED_TABLES.into_iter().chain(NEW_SCOPED_TABLES) {
+            db.execute_unprepared(&format!(
+                r#"
+                CREATE POLICY workspace_isolation ON {name}
+                USING (
+                    workspace_id = nullif(current_setting('olivier.workspace_id', true), '')::bigint
+                )
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for name in NEW_SCOPED_TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                DROP POLICY IF EXISTS workspace_isolation ON {name};
+                ALTER TABLE {name} NO FORCE ROW LEVEL SECURITY;
+                ALTER TABLE {name} DISABLE ROW LEVEL SECURITY;
+                "#
+            ))
+            .await?;
+        }
+        for name in SCOPED_TABLES {
+            db.execute_unprepared(&format!(
+                r#"
+                DROP POLICY IF EXISTS workspace_isolation ON {name};
+                CREATE POLICY workspace_isolation ON {name}
+                USING (
+                    nullif(current_setting('olivier.workspace_id', true), '') IS NULL
+                    OR workspace_id = nullif(current_setting('olivier.workspace_id', true), '')::bigint
+                )
+                "#
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use entity::lists::Model as ListsModel;
+use entity::shares::Column as SharesColumn;
+use entity::shares::Entity as SharesEntity;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use entity::todos::Model as TodosModel;
+use entity::workspace_members::Entity as WorkspaceMembersEntity;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+
+use crate::{
+    dto::Role,
+    error::{AppResult, ServiceError},
+};
+
+// Todos of the workspace the user owns or that are shared with them, directly or through a list
+pub fn visible_todos(workspace_id: i64, user_id: i64) -> Condition {
+    let shared_todos = Query::select()
+        .column(SharesColumn::TodoId)
+        .from(SharesEntity)
+        .and_where(SharesColumn::UserId.eq(user_id))
+        .and_where(SharesColumn::TodoId.is_not_null())
+        .to_owned();
+    let shared_lists = Query::select()
+        .columns([SharesColumn::ListId, SharesColumn::OwnerId])
+        .from(SharesEntity)
+        .and_where(SharesColumn::UserId.eq(user_id))
+        .and_where(SharesColumn::ListId.is_not_null())
+        .to_owned();
+
+    Condition::all()
+        .add(TodosColumn::WorkspaceId.eq(workspace_id))
+        .add(
+            Condition::any()
+                .add(TodosColumn::OwnerId.eq(user_id))
+                .add(TodosColumn::Id.in_subquery(shared_todos))
+                .add(
+                    Expr::tuple([
+                        Expr::col((TodosEntity, TodosColumn::ListId)).into(),
+                        Expr::col((TodosEntity, TodosColumn::OwnerId)).into(),
+                    ])
+                    .in_subquery(shared_lists),
+                ),
+        )
+}
+
+// The highest role the user holds on the todo, if any
+pub async fn todo_role(
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    todo: &TodosModel,
+) -> AppResult<Option<Role>> {
+    if todo.owner_id == Some(user_id) {
+        return Ok(Some(Role::Owner));
+    }
+
+    let mut target = Condition::any().add(SharesColumn::TodoId.eq(todo.id));
+    if let (Some(list_id), Some(owner_id)) = (todo.list_id, todo.owner_id) {
+        target = target.add(
+            SharesColumn::ListId
+                .eq(list_id)
+                .and(SharesColumn::OwnerId.eq(owner_id)),
+        );
+    }
+    let shares = SharesEntity::find()
+        .filter(SharesColumn::UserId.eq(user_id))
+        .filter(target)
+        .all(db)
+        .await?;
+
+    Ok(shares.into_iter().map(|share| Role::from(share.role)).max())
+}
+
+// The highest role the user holds on the list itself, admins of its workspace act as its owner
+pub async fn list_role(
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    list: &ListsModel,
+) -> AppResult<Option<Role>> {
+    if list.owner_id == Some(user_id) {
+        return Ok(Some(Role::Owner));
+    }
+    let admin = WorkspaceMembersEntity::find_by_id((list.workspace_id, user_id))
+        .one(db)
+        .await?
+        .map_or(false, |member| member.admin);
+    if admin {
+        return Ok(Some(Role::Owner));
+    }
+
+    // only a share of the owner covers the list, the others cover just the todos of the sharer
+    let shares = match list.owner_id {
+        Some(owner_id) => {
+            SharesEntity::find()
+                .filter(SharesColumn::UserId.eq(user_id))
+                .filter(SharesColumn::ListId.eq(list.id))
+                .filter(SharesColumn::OwnerId.eq(owner_id))
+                .all(db)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(shares.into_iter().map(|share| Role::from(share.role)).max())
+}
+
+// Every access to a single todo goes through here. Users without any role are told the todo
+// does not exist, so sharing does not leak which ids are taken.
+pub async fn authorize(
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    todo: &TodosModel,
+    required: Role,
+) -> AppResult<()> {
+    match todo_role(db, user_id, todo).await? {
+        None => Err(ServiceError::TodoNotFoundError(todo.id)),
+        Some(role) if role < required => Err(ServiceError::Forbidden(todo.id, required)),
+        Some(_) => Ok(()),
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::Utc;
+use jsonwebtoken::{
+    errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use entity::refresh_tokens::Column as RefreshTokensColumn;
+use entity::refresh_tokens::Entity as RefreshTokensEntity;
+
+use crate::{
+    config::{AuthConfig, JwtConfig},
+    error::{AppResult, ServiceError, UnauthorizedReason},
+};
+
+// Claims of an access token, enough to serve a request without looking the user up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// user id
+    pub sub: String,
+    pub email: String,
+    /// workspace the token is limited to, tokens without it work in every workspace of the user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<i64>,
+    /// refresh token family of the login, revoking it ends the access tokens issued for it too
+    pub sid: String,
+    pub iss: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+// Keys signing and checking access tokens, loaded once at startup
+pub struct JwtKeys {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    algorithm: Algorithm,
+    issuer: String,
+    lifetime: i64,
+}
+
+impl JwtKeys {
+    pub fn new(config: &AuthConfig) -> Result<Self> {
+        let (encoding, decoding, algorithm) = match &config.jwt {
+            JwtConfig::Hs256 { secret } => (
+                EncodingKey::from_secret(secret.as_bytes()),
+                DecodingKey::from_secret(secret.as_bytes()),
+                Algorithm::HS256,
+            ),
+            JwtConfig::Rs256 {
+                private_key,
+                public_key,
+            } => (
+                EncodingKey::from_rsa_pem(&std::fs::read(private_key)?)?,
+                DecodingKey::from_rsa_pem(&std::fs::read(public_key)?)?,
+                Algorithm::RS256,
+            ),
+        };
+
+        Ok(Self {
+            encoding,
+            decoding,
+            algorithm,
+            issuer: config.issuer.clone(),
+            lifetime: config.access_token_lifetime,
+        })
+    }
+
+    pub fn issue(
+        &self,
+        user_id: i64,
+        email: &str,
+        workspace_id: Option<i64>,
+        family: &str,
+    ) -> AppResult<String> {
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            sub: user_id.to_string(),
+            email: email.to_owned(),
+            workspace_id,
+            sid: family.to_owned(),
+            iss: self.issuer.clone(),
+            iat: now,
+            exp: now + self.lifetime,
+        };
+
+        Ok(jsonwebtoken::encode(
+            &Header::new(self.algorithm),
+            &claims,
+            &self.encoding,
+        )?)
+    }
+
+    // Only the configured algorithm is accepted, so an HS256 token cannot pass as RS256 one
+    pub fn verify(&self, token: &str) -> AppResult<Claims> {
+        let mut validation = Validation::new(self.algorithm);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub", "sid"]);
+
+        jsonwebtoken::decode(token, &self.decoding, &validation)
+            .map(|data: TokenData<Claims>| data.claims)
+            .map_err(|err| {
+                ServiceError::Unauthorized(match err.kind() {
+                    ErrorKind::ExpiredSignature => UnauthorizedReason::ExpiredToken,
+                    _ => UnauthorizedReason::InvalidToken,
+                })
+            })
+    }
+
+    pub fn lifetime(&self) -> i64 {
+        self.lifetime
+    }
+}
+
+// A login is over once every refresh token of its family is revoked, by logging out or because a
+// used one showed up again. Rotating leaves the newest token of the family unrevoked.
+pub async fn check_login(db: &impl ConnectionTrait, claims: &Claims) -> AppResult<()> {
+    let live = RefreshTokensEntity::find()
+        .filter(RefreshTokensColumn::Family.eq(&claims.sid))
+        .filter(RefreshTokensColumn::RevokedAt.is_null())
+        .count(db)
+        .await?;
+    if live == 0 {
+        return Err(ServiceError::Unauthorized(UnauthorizedReason::RevokedToken));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,113 @@
+mod access;
+mod api_key;
+mod jwt;
+mod oidc;
+mod password;
+mod token;
+mod workspace;
+
+pub use access::*;
+pub use api_key::*;
+pub use jwt::*;
+pub use oidc::*;
+pub use password::*;
+pub use token::*;
+pub use workspace::*;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::{
+    headers::{authorization::Bearer, Authorization},
+    TypedHeader,
+};
+use http::{request::Parts, HeaderName};
+
+use entity::sea_orm_active_enums::ApiKeyScope;
+
+use crate::{
+    error::{AppResult, ServiceError, UnauthorizedReason},
+    server::AppState,
+};
+
+// Alternative to sending an API key as bearer token
+pub const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+// The user a request is made by, resolved by `authenticate`
+#[derive(Debug, Clone)]
+pub struct CurrentUser {
+    pub id: i64,
+    pub email: String,
+    /// workspace the credentials are limited to
+    pub workspace_id: Option<i64>,
+    /// api key the request is made with
+    pub api_key_id: Option<i64>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = ServiceError;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or(ServiceError::Unauthorized(UnauthorizedReason::MissingToken))
+    }
+}
+
+// Rejects requests without a valid access token or API key before they reach a handler.
+// Access tokens carry the user, only whether their login was revoked is looked up.
+pub async fn authenticate(
+    State(state): State<AppState>,
+    bearer: Option<TypedHeader<Authorization<Bearer>>>,
+    mut request: Request,
+    next: Next,
+) -> AppResult<Response> {
+    let api_key = match request.headers().get(API_KEY_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .map_err(|_| ServiceError::Unauthorized(UnauthorizedReason::InvalidApiKey))?
+                .to_owned(),
+        ),
+        None => bearer
+            .as_ref()
+            .map(|bearer| bearer.token())
+            .filter(|token| is_api_key(token))
+            .map(str::to_owned),
+    };
+
+    let user = match api_key {
+        Some(api_key) => {
+            let (user, scope) = verify_api_key(&*state.database, &api_key).await?;
+            if scope == ApiKeyScope::Read && !request.method().is_safe() {
+                return Err(ServiceError::ReadOnlyApiKeyError);
+            }
+            user
+        }
+        None => {
+            let bearer =
+                bearer.ok_or(ServiceError::Unauthorized(UnauthorizedReason::MissingToken))?;
+            let claims = state.jwt.verify(bearer.token())?;
+            check_login(&*state.database, &claims).await?;
+            CurrentUser {
+                id: claims
+                    .sub
+                    .parse()
+                    .map_err(|_| ServiceError::Unauthorized(UnauthorizedReason::InvalidToken))?,
+                email: claims.email,
+                workspace_id: claims.workspace_id,
+                api_key_id: None,
+            }
+        }
+    };
+
+    request.extensions_mut().insert(user);
+
+    Ok(next.run(request).await)
+}
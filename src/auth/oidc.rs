@@ -0,0 +1,247 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use jsonwebtoken::{jwk::JwkSet, Algorithm, DecodingKey, TokenData, Validation};
+use reqwest::Url;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::{
+    config::OidcConfig,
+    error::{AppResult, ServiceError, UnauthorizedReason},
+};
+
+use super::token_hash;
+
+// Signing keys are fetched again for an unknown key id at most this often
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+// The parts of `/.well-known/openid-configuration` a login needs
+#[derive(Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+}
+
+// S256 code challenge of a verifier, see RFC 7636 section 4.2
+pub fn code_challenge(code_verifier: &str) -> String {
+    token_hash(code_verifier)
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenEndpointResponse {
+    id_token: Option<String>,
+    error: Option<String>,
+}
+
+struct Cached<T> {
+    value: Arc<T>,
+    fetched_at: Instant,
+}
+
+// Client of the configured identity provider, its configuration and keys are fetched on first
+// use and cached, so the service starts even while the provider is down
+pub struct OidcClient {
+    config: OidcConfig,
+    http: reqwest::Client,
+    provider: RwLock<Option<Cached<ProviderMetadata>>>,
+    jwks: RwLock<Option<Cached<JwkSet>>>,
+}
+
+impl OidcClient {
+    pub fn new(config: &OidcConfig) -> Result<Self> {
+        Ok(Self {
+            config: config.clone(),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+            provider: RwLock::new(None),
+            jwks: RwLock::new(None),
+        })
+    }
+
+    pub fn login_lifetime(&self) -> i64 {
+        self.config.login_lifetime
+    }
+
+    fn cache_lifetime(&self) -> Duration {
+        Duration::from_secs(self.config.discovery_cache_lifetime)
+    }
+
+    pub async fn provider(&self) -> AppResult<Arc<ProviderMetadata>> {
+        if let Some(cached) = &*self.provider.read().await {
+            if cached.fetched_at.elapsed() < self.cache_lifetime() {
+                return Ok(cached.value.clone());
+            }
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer_url.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self
+            .http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        // required by OpenID Connect Discovery section 4.3, tokens are checked against it
+        if metadata.issuer != self.config.issuer_url {
+            return Err(ServiceError::OidcProviderError(format!(
+                "issuer {} does not match the configured {}",
+                metadata.issuer, self.config.issuer_url
+            )));
+        }
+
+        let value = Arc::new(metadata);
+        *self.provider.write().await = Some(Cached {
+            value: value.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(value)
+    }
+
+    // Keys are fetched again once the cache is stale, or early for a key id not seen before
+    // since providers publish new keys ahead of using them
+    async fn jwks(&self, kid: Option<&str>) -> AppResult<Arc<JwkSet>> {
+        if let Some(cached) = &*self.jwks.read().await {
+            let age = cached.fetched_at.elapsed();
+            let known = match kid {
+                Some(kid) => cached.value.find(kid).is_some(),
+                None => true,
+            };
+            if age < self.cache_lifetime() && (known || age < JWKS_MIN_REFRESH) {
+                return Ok(cached.value.clone());
+            }
+        }
+
+        let provider = self.provider().await?;
+        let jwks: JwkSet = self
+            .http
+            .get(&provider.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let value = Arc::new(jwks);
+        *self.jwks.write().await = Some(Cached {
+            value: value.clone(),
+            fetched_at: Instant::now(),
+        });
+        Ok(value)
+    }
+
+    // Where the browser is sent to log in, see RFC 7636 for the code challenge
+    pub async fn authorization_url(
+        &self,
+        state: &str,
+        code_challenge: &str,
+        nonce: &str,
+    ) -> AppResult<String> {
+        let provider = self.provider().await?;
+        let mut url = Url::parse(&provider.authorization_endpoint).map_err(|err| {
+            ServiceError::OidcProviderError(format!("invalid authorization endpoint: {}", err))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url.into())
+    }
+
+    // Trades the authorization code for the ID token of the user
+    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> AppResult<String> {
+        let provider = self.provider().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.config.redirect_uri),
+            ("client_id", &self.config.client_id),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            form.push(("client_secret", secret));
+        }
+
+        let res: TokenEndpointResponse = self
+            .http
+            .post(&provider.token_endpoint)
+            .form(&form)
+            .send()
+            .await?
+            .json()
+            .await?;
+        match res {
+            TokenEndpointResponse {
+                id_token: Some(id_token),
+                ..
+            } => Ok(id_token),
+            // an expired or already used code is the most likely cause
+            TokenEndpointResponse {
+                error: Some(error), ..
+            } => Err(ServiceError::OidcLoginDeniedError(error)),
+            _ => Err(ServiceError::OidcProviderError(
+                "token response without id_token".to_owned(),
+            )),
+        }
+    }
+
+    // Checks signature, issuer, audience, expiry and the nonce of the login it belongs to
+    pub async fn verify_id_token(&self, id_token: &str, nonce: &str) -> AppResult<IdTokenClaims> {
+        let invalid = || ServiceError::Unauthorized(UnauthorizedReason::InvalidIdToken);
+
+        let header = jsonwebtoken::decode_header(id_token).map_err(|_| invalid())?;
+        // a shared secret is never used to sign id tokens here, so an HS256 token is forged
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(invalid());
+        }
+        let jwks = self.jwks(header.kid.as_deref()).await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(invalid)?;
+        let key = DecodingKey::from_jwk(jwk).map_err(|_| invalid())?;
+
+        let provider = self.provider().await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&provider.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub", "aud"]);
+
+        let claims = jsonwebtoken::decode(id_token, &key, &validation)
+            .map(|data: TokenData<IdTokenClaims>| data.claims)
+            .map_err(|_| invalid())?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid());
+        }
+
+        Ok(claims)
+    }
+}
@@ -0,0 +1,33 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+
+use crate::error::AppResult;
+
+// Argon2id in PHC string format, hashed off the async runtime since it is slow on purpose
+pub async fn hash_password(password: String) -> AppResult<String> {
+    let res = tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await?;
+
+    Ok(res?)
+}
+
+pub async fn verify_password(password: String, hash: String) -> AppResult<bool> {
+    let res = tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash)?;
+        match Argon2::default().verify_password(password.as_bytes(), &hash) {
+            Ok(()) => Ok(true),
+            Err(argon2::password_hash::Error::Password) => Ok(false),
+            Err(err) => Err(err),
+        }
+    })
+    .await?;
+
+    Ok(res?)
+}
@@ -0,0 +1,145 @@
+use axum::{async_trait, extract::FromRequestParts};
+use http::{header, request::Parts, HeaderMap, HeaderName};
+
+use entity::workspace_members::Column as WorkspaceMembersColumn;
+use entity::workspace_members::Entity as WorkspaceMembersEntity;
+use entity::workspaces::Column as WorkspacesColumn;
+use entity::workspaces::Entity as WorkspacesEntity;
+use sea_orm::sea_query::SimpleExpr;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseTransaction, DbBackend, EntityTrait, QueryFilter,
+    Statement, TransactionTrait,
+};
+
+use crate::{
+    auth::CurrentUser,
+    config::TenancyConfig,
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+// Alternative to a subdomain naming the workspace of a request
+pub const WORKSPACE_HEADER: HeaderName = HeaderName::from_static("x-workspace");
+
+// The workspace a request is made in, the user being a member of it
+#[derive(Debug, Clone)]
+pub struct CurrentWorkspace {
+    pub id: i64,
+    pub slug: String,
+}
+
+impl CurrentWorkspace {
+    // Transactions of requests in the workspace, kept to its rows by PostgreSQL as well. Every
+    // query on the data of a workspace runs in one, outside of them row level security lets no
+    // row of it pass.
+    pub async fn begin(&self, state: &AppState) -> AppResult<DatabaseTransaction> {
+        let txn = state.database.begin().await?;
+        txn.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT set_config('olivier.workspace_id', $1, true)",
+            [self.id.to_string().into()],
+        ))
+        .await?;
+
+        Ok(txn)
+    }
+}
+
+// Credentials limited to a workspace win over what the request names, naming another one is
+// refused instead of silently ignored. Workspaces the user is no member of do not exist for them.
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentWorkspace {
+    type Rejection = ServiceError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(workspace) = parts.extensions.get::<CurrentWorkspace>() {
+            return Ok(workspace.clone());
+        }
+
+        let user = CurrentUser::from_request_parts(parts, state).await?;
+        let requested = requested_workspace(&state.config.tenancy, &parts.headers);
+        let workspace = match user.workspace_id {
+            Some(id) => {
+                let workspace =
+                    find_workspace(&*state.database, user.id, WorkspacesColumn::Id.eq(id))
+                        .await?
+                        .ok_or(ServiceError::WorkspaceNotFoundError(id.to_string()))?;
+                if matches!(&requested, Some(slug) if *slug != workspace.slug) {
+                    return Err(ServiceError::WorkspaceMismatchError(workspace.slug));
+                }
+                workspace
+            }
+            None => {
+                let slug =
+                    requested.unwrap_or_else(|| state.config.tenancy.default_workspace.clone());
+                find_workspace(&*state.database, user.id, WorkspacesColumn::Slug.eq(&slug))
+                    .await?
+                    .ok_or(ServiceError::WorkspaceNotFoundError(slug))?
+            }
+        };
+
+        parts.extensions.insert(workspace.clone());
+        Ok(workspace)
+    }
+}
+
+// Slug of the workspace named by the `X-Workspace` header, or else by the subdomain of the host
+pub fn requested_workspace(config: &TenancyConfig, headers: &HeaderMap) -> Option<String> {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty())
+    };
+
+    header(WORKSPACE_HEADER).or_else(|| {
+        let base_domain = config.base_domain.as_deref()?;
+        let host = header(header::HOST)?;
+        let host = host.split(':').next().unwrap_or_default();
+        host.strip_suffix(base_domain)?
+            .strip_suffix('.')
+            .filter(|slug| !slug.is_empty() && !slug.contains('.'))
+            .map(str::to_owned)
+    })
+}
+
+// Logins made in a named workspace hand out tokens limited to it
+pub async fn login_workspace(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    headers: &HeaderMap,
+) -> AppResult<Option<i64>> {
+    match requested_workspace(&state.config.tenancy, headers) {
+        Some(slug) => find_workspace(db, user_id, WorkspacesColumn::Slug.eq(&slug))
+            .await?
+            .map(|workspace| Some(workspace.id))
+            .ok_or(ServiceError::WorkspaceNotFoundError(slug)),
+        None => Ok(None),
+    }
+}
+
+// The workspace matching the condition, if the user is a member of it
+pub async fn find_workspace(
+    db: &impl ConnectionTrait,
+    user_id: i64,
+    condition: SimpleExpr,
+) -> AppResult<Option<CurrentWorkspace>> {
+    let res = WorkspaceMembersEntity::find()
+        .filter(WorkspaceMembersColumn::UserId.eq(user_id))
+        .find_also_related(WorkspacesEntity)
+        .filter(condition)
+        .one(db)
+        .await?;
+
+    Ok(res.and_then(|(_, workspace)| {
+        workspace.map(|workspace| CurrentWorkspace {
+            id: workspace.id,
+            slug: workspace.slug,
+        })
+    }))
+}
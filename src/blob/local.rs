@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use std::{io, path::PathBuf};
+use tokio::{fs, io::BufWriter};
+
+use crate::blob::{BlobReader, BlobStore, BlobWriter};
+
+// Keeps every blob in a file below the root directory
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub async fn new(root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&root).await?;
+        Ok(Self { root })
+    }
+
+    // Blobs are spread over subdirectories named after the first characters of their key
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        if key.len() < 3 || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid blob key {}", key),
+            ));
+        }
+        Ok(self.root.join(&key[..2]).join(key))
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn create(&self, key: &str) -> io::Result<BlobWriter> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .await?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    async fn open(&self, key: &str) -> io::Result<BlobReader> {
+        let file = fs::File::open(self.path(key)?).await?;
+        Ok(Box::new(file))
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path(key)?).await {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+mod local;
+
+pub use local::*;
+
+use async_trait::async_trait;
+use std::{io, sync::Arc};
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::config::BlobStoreConfig;
+
+pub type BlobReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type BlobWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+// Storage of attachment contents, which are streamed in and out rather than held in memory
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    // The blob is complete once the writer has been shut down
+    async fn create(&self, key: &str) -> io::Result<BlobWriter>;
+
+    async fn open(&self, key: &str) -> io::Result<BlobReader>;
+
+    // Deleting a missing blob succeeds, so cleanups can be retried
+    async fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+pub async fn new(config: &BlobStoreConfig) -> io::Result<Arc<dyn BlobStore>> {
+    match config {
+        BlobStoreConfig::Local { root } => Ok(Arc::new(LocalBlobStore::new(root.clone()).await?)),
+    }
+}
@@ -0,0 +1,36 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttachmentsConfig {
+    pub max_size: u64,
+    pub allowed_content_types: Vec<String>,
+    pub store: BlobStoreConfig,
+}
+
+impl AttachmentsConfig {
+    // An allowed type ending in `/*` accepts every subtype of it
+    pub fn allows_content_type(&self, content_type: &str) -> bool {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        self.allowed_content_types.iter().any(|allowed| {
+            let allowed = allowed.to_ascii_lowercase();
+            match allowed.strip_suffix("/*") {
+                Some(prefix) => essence
+                    .strip_prefix(prefix)
+                    .map_or(false, |rest| rest.starts_with('/') && rest.len() > 1),
+                None => essence == allowed,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum BlobStoreConfig {
+    Local { root: PathBuf },
+}
@@ -0,0 +1,35 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuthConfig {
+    pub access_token_lifetime: i64,
+    pub refresh_token_lifetime: i64,
+    pub issuer: String,
+    pub jwt: JwtConfig,
+    /// single sign-on is turned off without it
+    pub oidc: Option<OidcConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "algorithm")]
+pub enum JwtConfig {
+    #[serde(rename = "HS256")]
+    Hs256 { secret: String },
+    #[serde(rename = "RS256")]
+    Rs256 {
+        private_key: PathBuf,
+        public_key: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub discovery_cache_lifetime: u64,
+    pub login_lifetime: i64,
+}
@@ -1,10 +1,24 @@
+mod attachments;
+mod auth;
 mod database;
 mod log;
+mod ordering;
+mod pagination;
+mod precondition;
+mod rate_limit;
 mod service;
+mod tenancy;
 
+pub use attachments::*;
+pub use auth::*;
 pub use database::*;
 pub use log::*;
+pub use ordering::*;
+pub use pagination::*;
+pub use precondition::*;
+pub use rate_limit::*;
 pub use service::*;
+pub use tenancy::*;
 
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
@@ -16,6 +30,13 @@ pub struct AppConfig {
     pub log: LogConfig,
     pub service: ServiceConfig,
     pub database: DatabaseConfig,
+    pub pagination: PaginationConfig,
+    pub precondition: PreconditionConfig,
+    pub ordering: OrderingConfig,
+    pub attachments: AttachmentsConfig,
+    pub auth: AuthConfig,
+    pub tenancy: TenancyConfig,
+    pub rate_limit: RateLimitConfig,
 }
 
 pub fn new() -> Result<AppConfig, ConfigError> {
@@ -0,0 +1,6 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OrderingConfig {
+    pub rebalance_rank_length: usize,
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaginationConfig {
+    pub default_page_size: u64,
+    pub max_page_size: u64,
+}
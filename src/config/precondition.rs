@@ -0,0 +1,6 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PreconditionConfig {
+    pub require_if_match: bool,
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    /// take the client address from the last `X-Forwarded-For` entry, only safe behind a proxy
+    pub trust_forwarded_for: bool,
+    /// bucket of every route group without one of its own
+    pub default: BucketConfig,
+    #[serde(default)]
+    pub groups: HashMap<String, BucketConfig>,
+}
+
+impl RateLimitConfig {
+    pub fn bucket(&self, group: &str) -> &BucketConfig {
+        self.groups.get(group).unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BucketConfig {
+    /// requests a client can make in a burst
+    pub capacity: u32,
+    /// requests a client regains per second
+    pub refill_per_second: f64,
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TenancyConfig {
+    pub default_workspace: String,
+    pub join_default_workspace: bool,
+    /// resolving workspaces from subdomains is turned off without it
+    pub base_domain: Option<String>,
+}
@@ -0,0 +1,214 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDateTime as DateTime;
+use sea_orm::{sea_query::SimpleExpr, ActiveEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dto::{SortDirection, TodoPriority, TodoSort, TodoSortField},
+    error::{AppResult, ServiceError},
+};
+
+// Value of the sort column of the last todo of a page
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoCursorKey {
+    Id(i64),
+    Body(String),
+    Complated(bool),
+    CreatedAt(DateTime),
+    UpdatedAt(DateTime),
+    DueAt(Option<DateTime>),
+    Priority(TodoPriority),
+    CompletedAt(Option<DateTime>),
+    Rank(String),
+}
+
+impl TodoCursorKey {
+    fn new(field: TodoSortField, todo: &entity::todos::Model) -> Self {
+        match field {
+            TodoSortField::Id => Self::Id(todo.id),
+            TodoSortField::Body => Self::Body(todo.body.clone()),
+            TodoSortField::Complated => Self::Complated(todo.complated),
+            TodoSortField::CreatedAt => Self::CreatedAt(todo.created_at),
+            TodoSortField::UpdatedAt => Self::UpdatedAt(todo.updated_at),
+            TodoSortField::DueAt => Self::DueAt(todo.due_at),
+            TodoSortField::Priority => Self::Priority(todo.priority.into()),
+            TodoSortField::CompletedAt => Self::CompletedAt(todo.completed_at),
+            TodoSortField::Rank => Self::Rank(todo.rank.clone()),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::DueAt(None) | Self::CompletedAt(None))
+    }
+
+    fn field(&self) -> TodoSortField {
+        match self {
+            Self::Id(_) => TodoSortField::Id,
+            Self::Body(_) => TodoSortField::Body,
+            Self::Complated(_) => TodoSortField::Complated,
+            Self::CreatedAt(_) => TodoSortField::CreatedAt,
+            Self::UpdatedAt(_) => TodoSortField::UpdatedAt,
+            Self::DueAt(_) => TodoSortField::DueAt,
+            Self::Priority(_) => TodoSortField::Priority,
+            Self::CompletedAt(_) => TodoSortField::CompletedAt,
+            Self::Rank(_) => TodoSortField::Rank,
+        }
+    }
+}
+
+impl From<TodoCursorKey> for SimpleExpr {
+    fn from(value: TodoCursorKey) -> Self {
+        match value {
+            TodoCursorKey::Id(v) => v.into(),
+            TodoCursorKey::Body(v) | TodoCursorKey::Rank(v) => v.into(),
+            TodoCursorKey::Complated(v) => v.into(),
+            TodoCursorKey::CreatedAt(v) | TodoCursorKey::UpdatedAt(v) => v.into(),
+            TodoCursorKey::DueAt(v) | TodoCursorKey::CompletedAt(v) => v.into(),
+            // postgres compares enums by declaration order only against the enum type
+            TodoCursorKey::Priority(v) => entity::sea_orm_active_enums::Priority::from(v).as_enum(),
+        }
+    }
+}
+
+// Opaque position of the last item of a page ordered by id
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IdCursor {
+    pub id: i64,
+}
+
+impl IdCursor {
+    pub fn encode(&self) -> String {
+        // serializing a struct of plain values never fails
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    pub fn decode(cursor: &str) -> AppResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+            .ok_or_else(|| ServiceError::InvalidCursorError(cursor.to_owned()))
+    }
+}
+
+// Opaque position of the last todo of a page, clients only pass it back as `cursor`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TodoCursor {
+    pub key: TodoCursorKey,
+    pub direction: SortDirection,
+    pub id: i64,
+}
+
+impl TodoCursor {
+    pub fn new(sort: TodoSort, todo: &entity::todos::Model) -> Self {
+        Self {
+            key: TodoCursorKey::new(sort.field, todo),
+            direction: sort.direction,
+            id: todo.id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        // serializing a struct of plain values never fails
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    // A cursor is only meaningful for the sort order of the page it was taken from
+    pub fn decode(cursor: &str, sort: TodoSort) -> AppResult<Self> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+            .filter(|c| c.key.field() == sort.field && c.direction == sort.direction)
+            .ok_or_else(|| ServiceError::InvalidCursorError(cursor.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(field: TodoSortField, direction: SortDirection) -> TodoSort {
+        TodoSort { field, direction }
+    }
+
+    fn due_at() -> DateTime {
+        DateTime::parse_from_str("2024-05-01 12:30:00", "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn todo_cursor_round_trips() {
+        let cursor = TodoCursor {
+            key: TodoCursorKey::DueAt(Some(due_at())),
+            direction: SortDirection::Desc,
+            id: 42,
+        };
+
+        let decoded = TodoCursor::decode(
+            &cursor.encode(),
+            sort(TodoSortField::DueAt, SortDirection::Desc),
+        )
+        .unwrap();
+        assert!(matches!(decoded.key, TodoCursorKey::DueAt(Some(due)) if due == due_at()));
+        assert!(!decoded.key.is_null());
+        assert_eq!(decoded.direction, SortDirection::Desc);
+        assert_eq!(decoded.id, 42);
+    }
+
+    #[test]
+    fn todo_cursor_keeps_null_keys() {
+        for key in [TodoCursorKey::DueAt(None), TodoCursorKey::CompletedAt(None)] {
+            let field = key.field();
+            let cursor = TodoCursor {
+                key,
+                direction: SortDirection::Asc,
+                id: 7,
+            };
+
+            let decoded =
+                TodoCursor::decode(&cursor.encode(), sort(field, SortDirection::Asc)).unwrap();
+            assert!(decoded.key.is_null());
+            assert_eq!(decoded.id, 7);
+        }
+    }
+
+    #[test]
+    fn todo_cursor_is_only_valid_for_its_sort() {
+        let cursor = TodoCursor {
+            key: TodoCursorKey::Rank("i".to_owned()),
+            direction: SortDirection::Asc,
+            id: 1,
+        }
+        .encode();
+
+        assert!(TodoCursor::decode(&cursor, sort(TodoSortField::Rank, SortDirection::Asc)).is_ok());
+        assert!(
+            TodoCursor::decode(&cursor, sort(TodoSortField::Rank, SortDirection::Desc)).is_err()
+        );
+        assert!(
+            TodoCursor::decode(&cursor, sort(TodoSortField::Body, SortDirection::Asc)).is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_cursors_are_rejected() {
+        let sort = sort(TodoSortField::Id, SortDirection::Asc);
+        for cursor in ["", "not base64!", &URL_SAFE_NO_PAD.encode(b"{\"id\":1}")] {
+            assert!(matches!(
+                TodoCursor::decode(cursor, sort),
+                Err(ServiceError::InvalidCursorError(_))
+            ));
+        }
+        assert!(IdCursor::decode("%%").is_err());
+    }
+
+    #[test]
+    fn id_cursor_round_trips() {
+        let cursor = IdCursor { id: 9 }.encode();
+
+        assert_eq!(IdCursor::decode(&cursor).unwrap().id, 9);
+    }
+}
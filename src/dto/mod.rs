@@ -1,5 +1,7 @@
+mod cursor;
 mod request;
 mod response;
 
+pub use cursor::*;
 pub use request::*;
 pub use response::*;
@@ -1,6 +1,319 @@
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use axum::{async_trait, extract::FromRequestParts};
+use chrono::{NaiveDateTime as DateTime, TimeZone, Utc};
+use entity::sea_orm_active_enums::{ApiKeyScope, Priority, ShareRole};
 use garde::Validate;
-use serde::Deserialize;
-use utoipa::ToSchema;
+use http::request::Parts;
+use rrule::{RRule, RRuleError, RRuleSet, Tz, Unvalidated};
+use serde::{de::IntoDeserializer, Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::CurrentUser;
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetTodosQuery {
+    /// maximum number of todos to return, clamped to the configured max page size
+    #[garde(range(min = 1))]
+    pub limit: Option<u64>,
+    /// opaque cursor taken from `next_cursor` of the previous page
+    #[garde(skip)]
+    pub cursor: Option<String>,
+    /// only return todos with the given completion state
+    #[garde(skip)]
+    pub completed: Option<bool>,
+    #[garde(custom(not_after(&self.created_before)))]
+    pub created_after: Option<DateTime>,
+    #[garde(skip)]
+    pub created_before: Option<DateTime>,
+    #[garde(custom(not_after(&self.updated_before)))]
+    pub updated_after: Option<DateTime>,
+    #[garde(skip)]
+    pub updated_before: Option<DateTime>,
+    /// only return todos with the given priority
+    #[garde(skip)]
+    #[param(inline)]
+    pub priority: Option<TodoPriority>,
+    #[garde(custom(not_after(&self.due_before)))]
+    pub due_after: Option<DateTime>,
+    #[garde(skip)]
+    pub due_before: Option<DateTime>,
+    #[garde(custom(not_after(&self.completed_before)))]
+    pub completed_after: Option<DateTime>,
+    #[garde(skip)]
+    pub completed_before: Option<DateTime>,
+    /// case-insensitive substring the body must contain
+    #[garde(length(min = 1, max = 1024))]
+    pub body_contains: Option<String>,
+    /// comma separated tag names the todos must carry
+    #[garde(length(min = 1, max = 1024))]
+    #[param(example = "work,urgent")]
+    pub tag: Option<String>,
+    /// whether todos must carry any or all of the tags in `tag`, defaults to `any`
+    #[garde(skip)]
+    #[param(inline)]
+    pub tag_match: Option<TagMatch>,
+    /// sort order as `field:asc|desc`, defaults to the manual order `rank:asc`, todos without `due_at` or `completed_at` come last
+    #[garde(skip)]
+    #[param(value_type = Option<String>, example = "created_at:desc")]
+    pub sort: Option<TodoSort>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchTodosQuery {
+    /// search terms, supports quoted phrases, `or` and `-` exclusion
+    #[garde(length(min = 1, max = 256))]
+    pub q: String,
+    /// maximum number of results to return, clamped to the configured max page size
+    #[garde(range(min = 1))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OccurrencesQuery {
+    /// start of the preview window, defaults to now
+    #[garde(custom(not_after(&self.to)))]
+    pub from: Option<DateTime>,
+    /// end of the preview window, open when absent
+    #[garde(skip)]
+    pub to: Option<DateTime>,
+    /// maximum number of occurrences to return, clamped to the configured max page size
+    #[garde(range(min = 1))]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryQuery {
+    /// maximum number of events to return, clamped to the configured max page size
+    #[garde(range(min = 1))]
+    pub limit: Option<u64>,
+    /// opaque cursor taken from `next_cursor` of the previous page
+    #[garde(skip)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RevertQuery {
+    /// version from the history of the todo to restore
+    #[garde(range(min = 1))]
+    pub to_version: i32,
+}
+
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CommentsQuery {
+    /// maximum number of comments to return, clamped to the configured max page size
+    #[garde(range(min = 1))]
+    pub limit: Option<u64>,
+    /// opaque cursor taken from `next_cursor` of the previous page
+    #[garde(skip)]
+    pub cursor: Option<String>,
+}
+
+// Sent back by the identity provider, with either `code` or `error`
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OidcCallbackQuery {
+    /// authorization code to trade for the user's identity
+    #[garde(length(min = 1, max = 2048))]
+    pub code: Option<String>,
+    /// state of the login started at `/api/v1/auth/oidc/authorize`
+    #[garde(length(min = 1, max = 256))]
+    pub state: String,
+    /// reason the provider refused the login, e.g. `access_denied`
+    #[garde(length(max = 256))]
+    pub error: Option<String>,
+}
+
+fn not_after<'a>(
+    upper: &'a Option<DateTime>,
+) -> impl FnOnce(&Option<DateTime>, &()) -> garde::Result + 'a {
+    move |lower, _| match (lower, upper) {
+        (Some(lower), Some(upper)) if lower > upper => Err(garde::Error::new(format!(
+            "lower bound {lower} is after upper bound {upper}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+// Timestamps are stored as naive utc, so "now" is compared in utc as well
+fn not_in_past(value: &Option<DateTime>, _: &()) -> garde::Result {
+    match value {
+        Some(value) if *value < Utc::now().naive_utc() => {
+            Err(garde::Error::new(format!("{value} is in the past")))
+        }
+        _ => Ok(()),
+    }
+}
+
+// Recurring todos repeat from their due date, which the rule is checked against
+fn recurrence_rule<'a>(
+    due_at: &'a Option<DateTime>,
+) -> impl FnOnce(&Option<String>, &()) -> garde::Result + 'a {
+    move |rule, _| match (rule, due_at) {
+        (None, _) => Ok(()),
+        (Some(_), None) => Err(garde::Error::new("a recurring todo requires due_at")),
+        (Some(rule), Some(due_at)) => recurrence_set(rule, *due_at)
+            .map(|_| ())
+            .map_err(|err| garde::Error::new(err.to_string())),
+    }
+}
+
+// Occurrences of an RFC 5545 RRULE value like `FREQ=WEEKLY;BYDAY=MO`, all in utc
+pub fn recurrence_set(rule: &str, start: DateTime) -> Result<RRuleSet, RRuleError> {
+    rule.parse::<RRule<Unvalidated>>()?
+        .build(Tz::UTC.from_utc_datetime(&start))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TodoPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl From<TodoPriority> for Priority {
+    fn from(value: TodoPriority) -> Self {
+        match value {
+            TodoPriority::Low => Self::Low,
+            TodoPriority::Normal => Self::Normal,
+            TodoPriority::High => Self::High,
+            TodoPriority::Urgent => Self::Urgent,
+        }
+    }
+}
+
+impl From<Priority> for TodoPriority {
+    fn from(value: Priority) -> Self {
+        match value {
+            Priority::Low => Self::Low,
+            Priority::Normal => Self::Normal,
+            Priority::High => Self::High,
+            Priority::Urgent => Self::Urgent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+// Columns of `todos` that the list endpoint is allowed to sort by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSortField {
+    Id,
+    Body,
+    Complated,
+    CreatedAt,
+    UpdatedAt,
+    DueAt,
+    Priority,
+    CompletedAt,
+    /// manual order set through `:move`
+    #[default]
+    Rank,
+}
+
+impl TodoSortField {
+    pub fn is_nullable(&self) -> bool {
+        matches!(self, Self::DueAt | Self::CompletedAt)
+    }
+}
+
+impl From<TodoSortField> for entity::todos::Column {
+    fn from(value: TodoSortField) -> Self {
+        match value {
+            TodoSortField::Id => Self::Id,
+            TodoSortField::Body => Self::Body,
+            TodoSortField::Complated => Self::Complated,
+            TodoSortField::CreatedAt => Self::CreatedAt,
+            TodoSortField::UpdatedAt => Self::UpdatedAt,
+            TodoSortField::DueAt => Self::DueAt,
+            TodoSortField::Priority => Self::Priority,
+            TodoSortField::CompletedAt => Self::CompletedAt,
+            TodoSortField::Rank => Self::Rank,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl From<SortDirection> for sea_orm::Order {
+    fn from(value: SortDirection) -> Self {
+        match value {
+            SortDirection::Asc => Self::Asc,
+            SortDirection::Desc => Self::Desc,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TodoSort {
+    pub field: TodoSortField,
+    pub direction: SortDirection,
+}
+
+impl FromStr for TodoSort {
+    type Err = serde::de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (field, direction) = match s.split_once(':') {
+            Some((field, direction)) => (field, Some(direction)),
+            None => (s, None),
+        };
+
+        Ok(Self {
+            field: TodoSortField::deserialize(field.into_deserializer())?,
+            direction: match direction {
+                Some(direction) => SortDirection::deserialize(direction.into_deserializer())?,
+                None => SortDirection::default(),
+            },
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for TodoSort {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// Query parameters validated while they are extracted, so that invalid ones are rejected the same
+// way as malformed ones
+#[derive(Debug)]
+pub struct Validated<T>(pub T);
+
+impl<'de, T> Deserialize<'de> for Validated<T>
+where
+    T: Deserialize<'de> + Validate<Context = ()>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = T::deserialize(deserializer)?;
+        value.validate(&()).map_err(serde::de::Error::custom)?;
+        Ok(Self(value))
+    }
+}
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct NewTodoRequest {
@@ -8,21 +321,396 @@ pub struct NewTodoRequest {
     pub body: String,
     #[garde(skip)]
     pub complated: Option<bool>,
+    #[garde(custom(not_in_past))]
+    pub due_at: Option<DateTime>,
+    /// defaults to `normal`
+    #[garde(skip)]
+    pub priority: Option<TodoPriority>,
+    /// names of existing tags
+    #[garde(length(max = 32), inner(length(min = 1, max = 64)))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// id of an existing list, taken from the path when created through a list
+    #[garde(skip)]
+    pub list_id: Option<i64>,
+    /// id of the todo this one is a subtask of
+    #[garde(skip)]
+    pub parent_id: Option<i64>,
+    /// RFC 5545 RRULE value such as `FREQ=WEEKLY;BYDAY=MO`, repeating from `due_at`
+    #[garde(length(min = 1, max = 512), custom(recurrence_rule(&self.due_at)))]
+    pub recurrence: Option<String>,
+}
+
+// Who made a change and in which request, recorded in the history of a todo
+#[derive(Debug, Clone, Default)]
+pub struct Audit {
+    pub actor: Option<String>,
+    pub request_id: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Audit {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| {
+            parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_owned)
+        };
+
+        // set by the authentication middleware in front of every route that changes todos
+        Ok(Self {
+            actor: parts
+                .extensions
+                .get::<CurrentUser>()
+                .map(|user| user.email.clone()),
+            request_id: header("x-request-id"),
+        })
+    }
+}
+
+// `{id}:{method}` path segment of a custom method on a single resource
+#[derive(Debug)]
+pub struct CustomMethodTarget {
+    pub id: i64,
+    pub method: String,
+}
+
+impl<'de> Deserialize<'de> for CustomMethodTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let (id, method) = s
+            .split_once(':')
+            .ok_or_else(|| serde::de::Error::custom(format!("missing custom method in {s}")))?;
+
+        Ok(Self {
+            id: id.parse().map_err(serde::de::Error::custom)?,
+            method: method.to_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchMode {
+    /// stop at the first failing operation and roll back all of them
+    #[default]
+    Atomic,
+    /// run every operation and commit the successful ones
+    Partial,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchTodoOperation {
+    Create(NewTodoRequest),
+    Update {
+        id: i64,
+        /// expected current version, same as `If-Match` on a single PUT
+        version: Option<i32>,
+        /// full replacement, same as the body of a single PUT
+        todo: UpdateTodoRequest,
+    },
+    Delete {
+        id: i64,
+        /// expected current version, same as `If-Match` on a single DELETE
+        version: Option<i32>,
+    },
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct BatchTodosRequest {
+    #[garde(skip)]
+    #[serde(default)]
+    pub mode: BatchMode,
+    #[garde(length(min = 1, max = 1000))]
+    pub operations: Vec<BatchTodoOperation>,
 }
 
+// Full replacement of a todo, every field must be present
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct UpdateTodoRequest {
     #[garde(length(min = 1, max = 1024))]
-    pub body: Option<String>,
+    pub body: String,
     #[garde(skip)]
-    pub complated: Option<bool>,
+    pub complated: bool,
+    /// may be in the past, an overdue todo stays overdue when replaced
+    #[garde(skip)]
+    pub due_at: Option<DateTime>,
+    #[garde(skip)]
+    #[serde(default)]
+    pub priority: TodoPriority,
+    /// names of existing tags, replacing the current ones
+    #[garde(length(max = 32), inner(length(min = 1, max = 64)))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// id of an existing list, absent to move the todo out of its list
+    #[garde(skip)]
+    pub list_id: Option<i64>,
+    /// id of the todo this one is a subtask of, must not be one of its own subtasks
+    #[garde(skip)]
+    pub parent_id: Option<i64>,
+    /// RFC 5545 RRULE value, absent to stop repeating the todo
+    #[garde(length(min = 1, max = 512), custom(recurrence_rule(&self.due_at)))]
+    pub recurrence: Option<String>,
+}
+
+// Body of both creating and editing a comment
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CommentRequest {
+    #[garde(length(min = 1, max = 4096), custom(not_blank))]
+    pub body: String,
+}
+
+// Multipart body of uploading an attachment, only documents the form since it is streamed field by field
+#[allow(dead_code)]
+#[derive(Debug, ToSchema)]
+pub struct AttachmentUpload {
+    /// file contents, the part's filename and the media type recognised from the contents are stored along with it
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+fn not_blank(value: &str, _: &()) -> garde::Result {
+    match value.trim().is_empty() {
+        true => Err(garde::Error::new("must not be blank")),
+        false => Ok(()),
+    }
+}
+
+// Body of both registering and logging in
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CredentialsRequest {
+    /// compared case insensitively
+    #[garde(email, length(max = 254))]
+    pub email: String,
+    #[garde(length(min = 8, max = 1024))]
+    pub password: String,
+}
+
+// Body of creating an API key
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ApiKeyRequest {
+    /// tells keys apart, e.g. the script or CI job using it
+    #[garde(length(min = 1, max = 128), custom(not_blank))]
+    pub name: String,
+    #[garde(skip)]
+    pub scope: KeyScope,
+    /// the key stops working at this time, never expires when absent
+    #[garde(custom(not_in_past))]
+    pub expires_at: Option<DateTime>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyScope {
+    /// only safe methods like GET
+    Read,
+    /// everything the user can do
+    ReadWrite,
+}
+
+impl From<KeyScope> for ApiKeyScope {
+    fn from(value: KeyScope) -> Self {
+        match value {
+            KeyScope::Read => Self::Read,
+            KeyScope::ReadWrite => Self::ReadWrite,
+        }
+    }
+}
+
+impl From<ApiKeyScope> for KeyScope {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::Read => Self::Read,
+            ApiKeyScope::ReadWrite => Self::ReadWrite,
+        }
+    }
+}
+
+// Body of both refreshing and revoking a login
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RefreshRequest {
+    /// the refresh token last handed out, each one can be used once
+    #[garde(length(min = 1, max = 256))]
+    pub refresh_token: String,
+}
+
+// Body of both creating and renaming a tag
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct TagRequest {
+    #[garde(length(min = 1, max = 64), custom(tag_name))]
+    pub name: String,
+}
+
+// Commas separate names in the `tag` filter, which also trims the names
+fn tag_name(value: &str, _: &()) -> garde::Result {
+    if value.contains(',') {
+        return Err(garde::Error::new("must not contain a comma"));
+    }
+    if value.trim() != value {
+        return Err(garde::Error::new("must not start or end with whitespace"));
+    }
+    Ok(())
+}
+
+// Body of both creating and replacing a list
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ListRequest {
+    #[garde(length(min = 1, max = 256))]
+    pub name: String,
+    #[garde(length(max = 4096))]
+    pub description: Option<String>,
+}
+
+// Rights on a todo, each role includes the ones before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// read the todo along with its comments, attachments and history
+    Viewer,
+    /// change, move, trash, comment on and attach to the todo
+    Editor,
+    /// purge the todo and share it, held by the user who created it
+    Owner,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Owner => "owner",
+        })
+    }
+}
+
+impl From<ShareRole> for Role {
+    fn from(value: ShareRole) -> Self {
+        match value {
+            ShareRole::Viewer => Self::Viewer,
+            ShareRole::Editor => Self::Editor,
+        }
+    }
+}
+
+// Body of sharing either a todo, or every todo of the caller in a list
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ShareRequest {
+    /// email of the user to share with
+    #[garde(email, length(max = 254))]
+    pub email: String,
+    /// `viewer` or `editor`, sharing again with the same user changes the role
+    #[garde(custom(grantable))]
+    pub role: Role,
+    #[garde(custom(exactly_one_of(&self.list_id)))]
+    pub todo_id: Option<i64>,
+    #[garde(skip)]
+    pub list_id: Option<i64>,
 }
 
-impl From<UpdateTodoRequest> for NewTodoRequest {
-    fn from(value: UpdateTodoRequest) -> Self {
-        Self {
-            body: value.body.unwrap_or_default(),
-            complated: value.complated,
+impl ShareRequest {
+    pub fn share_role(&self) -> ShareRole {
+        match self.role {
+            Role::Editor => ShareRole::Editor,
+            _ => ShareRole::Viewer,
         }
     }
 }
+
+fn grantable(value: &Role, _: &()) -> garde::Result {
+    match value {
+        Role::Owner => Err(garde::Error::new("owner cannot be shared")),
+        _ => Ok(()),
+    }
+}
+
+fn exactly_one_of<'a>(
+    other: &'a Option<i64>,
+) -> impl FnOnce(&Option<i64>, &()) -> garde::Result + 'a {
+    move |value, _| match (value, other) {
+        (Some(_), None) | (None, Some(_)) => Ok(()),
+        _ => Err(garde::Error::new(
+            "exactly one of todo_id and list_id is required",
+        )),
+    }
+}
+
+// Body of creating a workspace
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct WorkspaceRequest {
+    /// lowercase letters, digits and dashes, doubles as the subdomain of the workspace
+    #[garde(custom(workspace_slug))]
+    pub slug: String,
+    #[garde(length(min = 1, max = 128))]
+    pub name: String,
+}
+
+// A DNS label, so every slug can be a subdomain
+fn workspace_slug(value: &str, _: &()) -> garde::Result {
+    let valid = (1..=63).contains(&value.len())
+        && value
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+        && !value.starts_with('-')
+        && !value.ends_with('-');
+    match valid {
+        true => Ok(()),
+        false => Err(garde::Error::new(
+            "must be 1 to 63 lowercase letters, digits or inner dashes",
+        )),
+    }
+}
+
+// Body of adding a member to a workspace, or changing whether they are an admin
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MemberRequest {
+    /// email of the user to add
+    #[garde(email, length(max = 254))]
+    pub email: String,
+    /// admins manage the members of the workspace
+    #[garde(skip)]
+    #[serde(default)]
+    pub admin: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListDeletePolicy {
+    /// refuse to delete a list that still has todos outside of the trash the user can see
+    #[default]
+    Block,
+    /// move the todos of the list the user may edit to the trash along with it
+    Cascade,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteListQuery {
+    /// what happens to the todos of the list, defaults to `block`
+    #[param(inline)]
+    pub on_delete: Option<ListDeletePolicy>,
+}
+
+// New position of a todo, between `after` and `before` when both are given
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct MoveTodoRequest {
+    /// id of the todo to move the todo in front of
+    #[garde(custom(neighbour_required(&self.after)))]
+    pub before: Option<i64>,
+    /// id of the todo to move the todo behind
+    #[garde(skip)]
+    pub after: Option<i64>,
+}
+
+fn neighbour_required<'a>(
+    after: &'a Option<i64>,
+) -> impl FnOnce(&Option<i64>, &()) -> garde::Result + 'a {
+    move |before, _| match (before, after) {
+        (None, None) => Err(garde::Error::new("either before or after is required")),
+        _ => Ok(()),
+    }
+}
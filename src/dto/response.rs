@@ -1,8 +1,69 @@
+use std::time::SystemTime;
+
 use axum::response::{IntoResponse, Response};
+use axum_extra::headers::{ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, LastModified};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
 use chrono::NaiveDateTime as DateTime;
+use entity::sea_orm_active_enums::TodoEventKind;
+use http::{HeaderMap, StatusCode};
 use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use utoipa::ToSchema;
 
+use crate::{
+    dto::{KeyScope, Role, TodoPriority},
+    error::ServiceError,
+};
+
+// Validators of a representation, sent with it and compared against conditional request headers
+pub trait CacheValidators {
+    fn etag(&self) -> ETag;
+
+    fn last_modified(&self) -> Option<DateTime>;
+
+    fn validator_headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.typed_insert(self.etag());
+        if let Some(modified) = self.last_modified() {
+            headers.typed_insert(LastModified::from(SystemTime::from(modified.and_utc())));
+        }
+        headers
+    }
+}
+
+// Answers with 304 Not Modified instead of the body when the client's copy is still fresh
+pub struct Conditional<T> {
+    body: T,
+    not_modified: bool,
+}
+
+impl<T: CacheValidators> Conditional<T> {
+    pub fn new(headers: &HeaderMap, body: T) -> Self {
+        // If-Modified-Since is only evaluated without If-None-Match, see RFC 9110 section 13.2.2
+        let not_modified = match headers.typed_get::<IfNoneMatch>() {
+            Some(if_none_match) => !if_none_match.precondition_passes(&body.etag()),
+            None => match (headers.typed_get::<IfModifiedSince>(), body.last_modified()) {
+                (Some(since), Some(modified)) => {
+                    !since.is_modified(SystemTime::from(modified.and_utc()))
+                }
+                _ => false,
+            },
+        };
+
+        Self { body, not_modified }
+    }
+}
+
+impl<T: CacheValidators + IntoResponse> IntoResponse for Conditional<T> {
+    fn into_response(self) -> Response {
+        match self.not_modified {
+            true => (StatusCode::NOT_MODIFIED, self.body.validator_headers()).into_response(),
+            false => self.body.into_response(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct ErrorResponse {
     pub code: i32,
@@ -33,21 +94,420 @@ pub struct TodoResponse {
     pub complated: bool,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    /// incremented on every change, also sent as the `ETag` header
+    pub version: i32,
+    /// set while the todo is in the trash
+    pub deleted_at: Option<DateTime>,
+    pub due_at: Option<DateTime>,
+    pub priority: TodoPriority,
+    /// set when the todo was last marked as complated
+    pub completed_at: Option<DateTime>,
+    /// names of the assigned tags, sorted
+    pub tags: Vec<String>,
+    pub list_id: Option<i64>,
+    pub parent_id: Option<i64>,
+    pub subtasks: SubtaskRollup,
+    /// RFC 5545 RRULE, handed over to the next occurrence once the todo is complated
+    pub recurrence: Option<String>,
+    pub comment_count: i64,
+    /// user the todo belongs to
+    pub owner_id: Option<i64>,
+    pub workspace_id: i64,
 }
 
-impl From<entity::todos::Model> for TodoResponse {
-    fn from(value: entity::todos::Model) -> Self {
+// Completion of the direct subtasks of a todo, subtasks in the trash are not counted
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct SubtaskRollup {
+    pub total: i64,
+    pub completed: i64,
+}
+
+impl TodoResponse {
+    pub fn version_etag(version: i32) -> ETag {
+        // a quoted number is always a valid entity tag
+        format!("\"{version}\"").parse().unwrap()
+    }
+}
+
+impl CacheValidators for TodoResponse {
+    fn etag(&self) -> ETag {
+        Self::version_etag(self.version)
+    }
+
+    fn last_modified(&self) -> Option<DateTime> {
+        Some(self.updated_at)
+    }
+}
+
+impl From<(entity::todos::Model, Vec<entity::tags::Model>)> for TodoResponse {
+    fn from((value, tags): (entity::todos::Model, Vec<entity::tags::Model>)) -> Self {
+        let mut tags: Vec<String> = tags.into_iter().map(|tag| tag.name).collect();
+        tags.sort();
+
         Self {
             id: value.id,
             body: value.body,
             complated: value.complated,
             created_at: value.created_at,
             updated_at: value.updated_at,
+            version: value.version,
+            deleted_at: value.deleted_at,
+            due_at: value.due_at,
+            priority: value.priority.into(),
+            completed_at: value.completed_at,
+            tags,
+            list_id: value.list_id,
+            parent_id: value.parent_id,
+            subtasks: SubtaskRollup::default(),
+            recurrence: value.recurrence,
+            comment_count: 0,
+            owner_id: value.owner_id,
+            workspace_id: value.workspace_id,
         }
     }
 }
 
 impl IntoResponse for TodoResponse {
+    fn into_response(self) -> Response {
+        (self.validator_headers(), axum::Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TodoTreeResponse {
+    #[serde(flatten)]
+    pub todo: TodoResponse,
+    /// subtasks outside of the trash, oldest first
+    pub children: Vec<TodoTreeResponse>,
+}
+
+impl IntoResponse for TodoTreeResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentResponse {
+    pub id: i64,
+    pub todo_id: i64,
+    /// user who wrote the comment, absent for comments older than their authors
+    pub author_id: Option<i64>,
+    pub body: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl From<entity::comments::Model> for CommentResponse {
+    fn from(value: entity::comments::Model) -> Self {
+        Self {
+            id: value.id,
+            todo_id: value.todo_id,
+            author_id: value.author_id,
+            body: value.body,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl IntoResponse for CommentResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentsResponse {
+    /// oldest first
+    pub comments: Vec<CommentResponse>,
+    /// cursor of the next page, absent on the last page
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl IntoResponse for CommentsResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserResponse {
+    pub id: i64,
+    pub email: String,
+    pub created_at: DateTime,
+}
+
+impl From<entity::users::Model> for UserResponse {
+    fn from(value: entity::users::Model) -> Self {
+        Self {
+            id: value.id,
+            email: value.email,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl IntoResponse for UserResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+// Shaped like an OAuth 2.0 token response, see RFC 6749 section 5.1
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenResponse {
+    /// sent back as `Authorization: Bearer <access_token>`
+    pub access_token: String,
+    /// always `Bearer`
+    pub token_type: String,
+    /// seconds until the access token expires
+    pub expires_in: i64,
+    /// trades for a new pair of tokens at `/api/v1/auth/refresh`, once
+    pub refresh_token: String,
+    pub user: UserResponse,
+}
+
+impl IntoResponse for TokenResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ShareResponse {
+    pub id: i64,
+    /// user whose todos are shared
+    pub owner_id: i64,
+    /// user the todos are shared with
+    pub user_id: i64,
+    pub todo_id: Option<i64>,
+    pub list_id: Option<i64>,
+    pub role: Role,
+    pub created_at: DateTime,
+}
+
+impl From<entity::shares::Model> for ShareResponse {
+    fn from(value: entity::shares::Model) -> Self {
+        Self {
+            id: value.id,
+            owner_id: value.owner_id,
+            user_id: value.user_id,
+            todo_id: value.todo_id,
+            list_id: value.list_id,
+            role: value.role.into(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl IntoResponse for ShareResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SharesResponse {
+    /// shares made by the user and shares made with the user, newest first
+    pub shares: Vec<ShareResponse>,
+}
+
+impl IntoResponse for SharesResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: i64,
+    pub name: String,
+    /// first part of the key, shown to tell keys apart
+    pub prefix: String,
+    pub scope: KeyScope,
+    pub created_at: DateTime,
+    pub expires_at: Option<DateTime>,
+    pub last_used_at: Option<DateTime>,
+    /// workspace the key is limited to, the one it was created in
+    pub workspace_id: Option<i64>,
+}
+
+impl From<entity::api_keys::Model> for ApiKeyResponse {
+    fn from(value: entity::api_keys::Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            prefix: value.prefix,
+            scope: value.scope.into(),
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            last_used_at: value.last_used_at,
+            workspace_id: value.workspace_id,
+        }
+    }
+}
+
+impl IntoResponse for ApiKeyResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct NewApiKeyResponse {
+    /// sent as `Authorization: Bearer <key>` or `X-Api-Key: <key>`, only ever shown here
+    pub key: String,
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+}
+
+impl IntoResponse for NewApiKeyResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeysResponse {
+    /// newest first
+    pub api_keys: Vec<ApiKeyResponse>,
+}
+
+impl IntoResponse for ApiKeysResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentResponse {
+    pub id: i64,
+    pub todo_id: i64,
+    pub file_name: String,
+    pub content_type: String,
+    /// size of the contents in bytes
+    pub size: i64,
+    /// hex encoded SHA-256 checksum of the contents
+    pub sha256: String,
+    pub created_at: DateTime,
+}
+
+impl From<entity::attachments::Model> for AttachmentResponse {
+    fn from(value: entity::attachments::Model) -> Self {
+        Self {
+            id: value.id,
+            todo_id: value.todo_id,
+            file_name: value.file_name,
+            content_type: value.content_type,
+            size: value.size,
+            sha256: value.sha256,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl IntoResponse for AttachmentResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttachmentsResponse {
+    /// oldest first
+    pub attachments: Vec<AttachmentResponse>,
+}
+
+impl IntoResponse for AttachmentsResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Created,
+    Updated,
+    Trashed,
+    Restored,
+    Purged,
+    Reverted,
+}
+
+impl From<TodoEventKind> for EventKind {
+    fn from(value: TodoEventKind) -> Self {
+        match value {
+            TodoEventKind::Created => Self::Created,
+            TodoEventKind::Updated => Self::Updated,
+            TodoEventKind::Trashed => Self::Trashed,
+            TodoEventKind::Restored => Self::Restored,
+            TodoEventKind::Purged => Self::Purged,
+            TodoEventKind::Reverted => Self::Reverted,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TodoEventResponse {
+    pub id: i64,
+    /// version of the todo after the change, before it for a purge
+    pub version: i32,
+    pub kind: EventKind,
+    /// `From` header of the request that made the change
+    pub actor: Option<String>,
+    /// `X-Request-Id` of the request that made the change
+    pub request_id: Option<String>,
+    /// todo as returned by the API before the change, absent on creation
+    pub before: Option<Value>,
+    /// todo as returned by the API after the change, absent on purge
+    pub after: Option<Value>,
+    /// RFC 6902 JSON Patch from `before` to `after`
+    pub diff: Value,
+    pub created_at: DateTime,
+}
+
+impl From<entity::todo_events::Model> for TodoEventResponse {
+    fn from(value: entity::todo_events::Model) -> Self {
+        Self {
+            id: value.id,
+            version: value.version,
+            kind: value.kind.into(),
+            actor: value.actor,
+            request_id: value.request_id,
+            before: value.before,
+            after: value.after,
+            diff: value.diff,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TodoHistoryResponse {
+    /// newest first
+    pub events: Vec<TodoEventResponse>,
+    /// cursor of the next page, absent on the last page
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl IntoResponse for TodoHistoryResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OccurrencesResponse {
+    /// due dates of the series inside the requested window, earliest first
+    pub occurrences: Vec<DateTime>,
+    pub has_more: bool,
+}
+
+impl IntoResponse for OccurrencesResponse {
     fn into_response(self) -> Response {
         axum::Json(self).into_response()
     }
@@ -56,9 +516,235 @@ impl IntoResponse for TodoResponse {
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TodosResponse {
     pub todos: Vec<TodoResponse>,
+    /// cursor of the next page, absent on the last page
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl CacheValidators for TodosResponse {
+    // weak, the page is compared by its json content rather than byte for byte
+    fn etag(&self) -> ETag {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        let hash = URL_SAFE_NO_PAD.encode(Sha256::digest(json));
+        // base64url never contains a quote, so the entity tag is always valid
+        format!("W/\"{hash}\"").parse().unwrap()
+    }
+
+    // removed todos do not move it, so clients should prefer If-None-Match for lists
+    fn last_modified(&self) -> Option<DateTime> {
+        self.todos.iter().map(|todo| todo.updated_at).max()
+    }
 }
 
 impl IntoResponse for TodosResponse {
+    fn into_response(self) -> Response {
+        (self.validator_headers(), axum::Json(self)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TodoSearchResultResponse {
+    #[serde(flatten)]
+    pub todo: TodoResponse,
+    pub rank: f32,
+    /// HTML escaped fragment of the body with matched terms wrapped in `<mark></mark>`
+    pub snippet: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TodoSearchResponse {
+    pub results: Vec<TodoSearchResultResponse>,
+}
+
+impl IntoResponse for TodoSearchResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagResponse {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+impl From<entity::tags::Model> for TagResponse {
+    fn from(value: entity::tags::Model) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl IntoResponse for TagResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagsResponse {
+    pub tags: Vec<TagResponse>,
+}
+
+impl IntoResponse for TagsResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListResponse {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// user who created the list, absent for lists older than their owners
+    pub owner_id: Option<i64>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    /// todos of the list that are not complated, the trash is not counted
+    pub open_count: i64,
+    /// todos of the list that are complated, the trash is not counted
+    pub completed_count: i64,
+}
+
+impl IntoResponse for ListResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListsResponse {
+    pub lists: Vec<ListResponse>,
+}
+
+impl IntoResponse for ListsResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchTodoResult {
+    /// http status the operation would have had as a single request
+    pub status: u16,
+    pub todo: Option<TodoResponse>,
+    pub error: Option<ErrorResponse>,
+}
+
+impl BatchTodoResult {
+    pub fn success(status: StatusCode, todo: Option<TodoResponse>) -> Self {
+        Self {
+            status: status.as_u16(),
+            todo,
+            error: None,
+        }
+    }
+}
+
+impl From<ServiceError> for BatchTodoResult {
+    fn from(value: ServiceError) -> Self {
+        Self {
+            status: value.get_status_code().as_u16(),
+            todo: None,
+            error: Some((&value).into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchTodosResponse {
+    /// whether the successful operations were persisted
+    pub committed: bool,
+    /// one result per operation, in request order
+    pub results: Vec<BatchTodoResult>,
+}
+
+impl IntoResponse for BatchTodosResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkspaceResponse {
+    pub id: i64,
+    pub slug: String,
+    pub name: String,
+    /// whether the user manages the members
+    pub admin: bool,
+    pub created_at: DateTime,
+}
+
+impl From<(entity::workspaces::Model, bool)> for WorkspaceResponse {
+    fn from((value, admin): (entity::workspaces::Model, bool)) -> Self {
+        Self {
+            id: value.id,
+            slug: value.slug,
+            name: value.name,
+            admin,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl IntoResponse for WorkspaceResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WorkspacesResponse {
+    /// workspaces the user is a member of, sorted by slug
+    pub workspaces: Vec<WorkspaceResponse>,
+}
+
+impl IntoResponse for WorkspacesResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MemberResponse {
+    pub user_id: i64,
+    pub email: String,
+    pub admin: bool,
+    /// when the user joined the workspace
+    pub created_at: DateTime,
+}
+
+impl From<(entity::workspace_members::Model, entity::users::Model)> for MemberResponse {
+    fn from((value, user): (entity::workspace_members::Model, entity::users::Model)) -> Self {
+        Self {
+            user_id: value.user_id,
+            email: user.email,
+            admin: value.admin,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl IntoResponse for MemberResponse {
+    fn into_response(self) -> Response {
+        axum::Json(self).into_response()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MembersResponse {
+    /// sorted by email
+    pub members: Vec<MemberResponse>,
+}
+
+impl IntoResponse for MembersResponse {
     fn into_response(self) -> Response {
         axum::Json(self).into_response()
     }
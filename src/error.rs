@@ -1,12 +1,16 @@
 use axum::{
-    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
-    http::StatusCode,
+    extract::{
+        multipart::{MultipartError, MultipartRejection},
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
 };
 use thiserror::Error;
 use utoipa::ToSchema;
 
-use crate::dto::ErrorResponse;
+use crate::dto::{ErrorResponse, Role};
+use crate::rate_limit::RateLimit;
 
 pub type AppResult<T, E = ServiceError> = core::result::Result<T, E>;
 
@@ -23,27 +27,323 @@ pub enum ServiceError {
     #[error("cannot find todo with id {0}")]
     TodoNotFoundError(i64),
 
+    #[error("cannot find tag with id {0}")]
+    TagNotFoundError(i64),
+
+    #[error("tag {0} already exists")]
+    TagNameConflictError(String),
+
+    #[error("unknown tags {0}")]
+    UnknownTagError(String),
+
+    #[error("cannot find list with id {0}")]
+    ListNotFoundError(i64),
+
+    #[error("cannot find version {1} of todo {0} in its history")]
+    VersionNotFoundError(i64, i32),
+
+    #[error("cannot revert todo {0} to version {1}: {2}")]
+    VersionNotRevertibleError(i64, i32, String),
+
+    #[error("cannot find comment with id {1} on todo {0}")]
+    CommentNotFoundError(i64, i64),
+
+    #[error("cannot find attachment with id {1} on todo {0}")]
+    AttachmentNotFoundError(i64, i64),
+
+    #[error("list with id {0} still has todos")]
+    ListNotEmptyError(i64),
+
+    #[error("unknown list {0}")]
+    UnknownListError(i64),
+
+    #[error("only the owner of list {0} or an admin of its workspace deletes it")]
+    ListOwnerRequiredError(i64),
+
+    #[error("only the owner of list {0}, an editor it is shared with or an admin of its workspace changes it")]
+    ListEditorRequiredError(i64),
+
+    #[error("unknown parent todo {0}")]
+    UnknownParentError(i64),
+
+    #[error(
+        "todo {0} cannot become a subtask of {1}, which is the todo itself or one of its subtasks"
+    )]
+    ParentCycleError(i64, i64),
+
+    #[error("cannot find neighbour todo with id {0}")]
+    MoveNeighbourNotFoundError(i64),
+
     #[error("{0}")]
-    JsonExtractorRejection(#[from] JsonRejection),
+    InvalidMoveError(String),
+
+    #[error("todo {0} does not recur")]
+    TodoNotRecurringError(i64),
+
+    #[error("invalid recurrence: {0}")]
+    RecurrenceError(#[from] rrule::RRuleError),
+
+    #[error("{0}")]
+    Unauthorized(UnauthorizedReason),
+
+    #[error("email {0} is already registered")]
+    EmailTakenError(String),
+
+    #[error("api key is read-only")]
+    ReadOnlyApiKeyError,
+
+    #[error("cannot find api key with id {0}")]
+    ApiKeyNotFoundError(i64),
+
+    #[error("todo {0} requires the {1} role")]
+    Forbidden(i64, Role),
+
+    #[error("comment {1} on todo {0} belongs to another user")]
+    CommentAuthorRequiredError(i64, i64),
+
+    #[error("cannot find share with id {0}")]
+    ShareNotFoundError(i64),
+
+    #[error("unknown user {0}")]
+    UnknownShareUserError(String),
+
+    #[error("{0}")]
+    InvalidShareError(String),
+
+    #[error("cannot find workspace {0}")]
+    WorkspaceNotFoundError(String),
+
+    #[error("credentials are limited to workspace {0}")]
+    WorkspaceMismatchError(String),
+
+    #[error("only admins manage the members of workspace {0}")]
+    WorkspaceAdminRequiredError(i64),
+
+    #[error("workspace {0} already exists")]
+    WorkspaceSlugTakenError(String),
+
+    #[error("unknown user {0}")]
+    UnknownMemberError(String),
+
+    #[error("workspace {0} needs at least one admin")]
+    LastWorkspaceAdminError(i64),
+
+    #[error("too many requests, retry in {} seconds", .0.reset)]
+    RateLimitedError(RateLimit),
+
+    #[error("single sign-on is not configured")]
+    OidcNotConfiguredError,
+
+    #[error("unknown or expired login state")]
+    InvalidOidcStateError,
+
+    #[error("identity provider denied the login: {0}")]
+    OidcLoginDeniedError(String),
+
+    #[error("identity provider did not share an email address")]
+    OidcMissingEmailError,
+
+    #[error("email {0} belongs to another account and is not verified by the identity provider")]
+    OidcEmailConflictError(String),
+
+    #[error("{0}")]
+    MultipartExtractorRejection(#[from] MultipartRejection),
 
     #[error("{0}")]
+    MultipartError(#[from] MultipartError),
+
+    #[error("missing multipart field file")]
+    MissingAttachmentFileError,
+
+    #[error("attachment is larger than {0} bytes")]
+    AttachmentTooLargeError(u64),
+
+    #[error("unsupported attachment media type {0}")]
+    UnsupportedAttachmentTypeError(String),
+
+    #[error("cannot find todo with id {0} in the trash")]
+    TrashedTodoNotFoundError(i64),
+
+    #[error("todo with id {0} is in the trash, restore it first")]
+    TodoTrashedError(i64),
+
+    #[error("{0}")]
+    JsonExtractorRejection(#[from] JsonRejection),
+
+    #[error("{}", .0.body_text())]
     QueryExtractorRejection(#[from] QueryRejection),
 
+    #[error("invalid cursor {0}")]
+    InvalidCursorError(String),
+
     #[error("{0}")]
     PathExtractorRejection(#[from] PathRejection),
+
+    #[error("unsupported patch media type {0}, expected application/merge-patch+json or application/json-patch+json")]
+    UnsupportedPatchMediaTypeError(String),
+
+    #[error("invalid patch document: {0}")]
+    InvalidPatchError(serde_json::Error),
+
+    #[error("{0}")]
+    PatchConflictError(#[from] json_patch::PatchError),
+
+    #[error("field {0} is read-only")]
+    ReadOnlyFieldError(String),
+
+    #[error("todo with id {0} does not match If-Match")]
+    PreconditionFailedError(i64),
+
+    #[error("modifying todo with id {0} requires If-Match")]
+    PreconditionRequiredError(i64),
+
+    #[error("unknown custom method {0}")]
+    CustomMethodNotFoundError(String),
+
+    #[error("aborted because operation {0} failed")]
+    BatchAbortedError(usize),
+
+    #[error("blob store: {0}")]
+    BlobStoreError(#[from] std::io::Error),
+
+    #[error("password hashing: {0}")]
+    PasswordHashError(#[from] argon2::password_hash::Error),
+
+    #[error("{0}")]
+    BlockingTaskError(#[from] tokio::task::JoinError),
+
+    #[error("token signing: {0}")]
+    TokenSigningError(#[from] jsonwebtoken::errors::Error),
+
+    #[error("identity provider request: {0}")]
+    OidcRequestError(#[from] reqwest::Error),
+
+    #[error("identity provider: {0}")]
+    OidcProviderError(String),
+}
+
+// Why a request is not authenticated, told apart by the internal code
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnauthorizedReason {
+    #[error("missing bearer token or api key")]
+    MissingToken,
+
+    #[error("invalid email or password")]
+    InvalidCredentials,
+
+    #[error("invalid bearer token")]
+    InvalidToken,
+
+    #[error("bearer token has expired")]
+    ExpiredToken,
+
+    #[error("bearer token belongs to a login that has ended")]
+    RevokedToken,
+
+    #[error("invalid or expired refresh token")]
+    InvalidRefreshToken,
+
+    #[error("refresh token was already used, every token of its login is revoked")]
+    RefreshTokenReused,
+
+    #[error("invalid api key")]
+    InvalidApiKey,
+
+    #[error("api key has expired")]
+    ExpiredApiKey,
+
+    #[error("invalid id token")]
+    InvalidIdToken,
+}
+
+impl UnauthorizedReason {
+    // `WWW-Authenticate` challenge, see RFC 6750 section 3
+    fn challenge(&self) -> HeaderValue {
+        match self {
+            UnauthorizedReason::InvalidToken
+            | UnauthorizedReason::ExpiredToken
+            | UnauthorizedReason::RevokedToken
+            | UnauthorizedReason::InvalidApiKey
+            | UnauthorizedReason::ExpiredApiKey => {
+                HeaderValue::from_static(r#"Bearer error="invalid_token""#)
+            }
+            _ => HeaderValue::from_static("Bearer"),
+        }
+    }
 }
 
 impl ServiceError {
-    fn get_status_code(&self) -> StatusCode {
+    pub fn get_status_code(&self) -> StatusCode {
         match self {
             // 4xx
             ServiceError::QueryExtractorRejection(_) => StatusCode::BAD_REQUEST,
+            ServiceError::InvalidCursorError(_) => StatusCode::BAD_REQUEST,
             ServiceError::PathExtractorRejection(_) => StatusCode::BAD_REQUEST,
             ServiceError::JsonExtractorRejection(_) => StatusCode::UNPROCESSABLE_ENTITY,
             ServiceError::InvalidInputError(_) => StatusCode::BAD_REQUEST,
             ServiceError::TodoNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::TrashedTodoNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::TagNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::TagNameConflictError(_) => StatusCode::CONFLICT,
+            ServiceError::UnknownTagError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::ListNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::VersionNotFoundError(_, _) => StatusCode::NOT_FOUND,
+            ServiceError::VersionNotRevertibleError(_, _, _) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::CommentNotFoundError(_, _) => StatusCode::NOT_FOUND,
+            ServiceError::AttachmentNotFoundError(_, _) => StatusCode::NOT_FOUND,
+            ServiceError::ListNotEmptyError(_) => StatusCode::CONFLICT,
+            ServiceError::UnknownListError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::ListOwnerRequiredError(_) => StatusCode::FORBIDDEN,
+            ServiceError::ListEditorRequiredError(_) => StatusCode::FORBIDDEN,
+            ServiceError::UnknownParentError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::ParentCycleError(_, _) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::MoveNeighbourNotFoundError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::InvalidMoveError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::TodoNotRecurringError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::RecurrenceError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ServiceError::EmailTakenError(_) => StatusCode::CONFLICT,
+            ServiceError::ReadOnlyApiKeyError => StatusCode::FORBIDDEN,
+            ServiceError::ApiKeyNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::Forbidden(_, _) => StatusCode::FORBIDDEN,
+            ServiceError::CommentAuthorRequiredError(_, _) => StatusCode::FORBIDDEN,
+            ServiceError::ShareNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::UnknownShareUserError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::InvalidShareError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::WorkspaceNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::WorkspaceMismatchError(_) => StatusCode::FORBIDDEN,
+            ServiceError::WorkspaceAdminRequiredError(_) => StatusCode::FORBIDDEN,
+            ServiceError::WorkspaceSlugTakenError(_) => StatusCode::CONFLICT,
+            ServiceError::UnknownMemberError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::LastWorkspaceAdminError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::RateLimitedError(_) => StatusCode::TOO_MANY_REQUESTS,
+            ServiceError::OidcNotConfiguredError => StatusCode::NOT_FOUND,
+            ServiceError::InvalidOidcStateError => StatusCode::BAD_REQUEST,
+            ServiceError::OidcLoginDeniedError(_) => StatusCode::FORBIDDEN,
+            ServiceError::OidcMissingEmailError => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::OidcEmailConflictError(_) => StatusCode::CONFLICT,
+            ServiceError::MultipartExtractorRejection(err) => err.status(),
+            ServiceError::MultipartError(err) => err.status(),
+            ServiceError::MissingAttachmentFileError => StatusCode::BAD_REQUEST,
+            ServiceError::AttachmentTooLargeError(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ServiceError::UnsupportedAttachmentTypeError(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServiceError::TodoTrashedError(_) => StatusCode::CONFLICT,
+            ServiceError::UnsupportedPatchMediaTypeError(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServiceError::InvalidPatchError(_) => StatusCode::BAD_REQUEST,
+            ServiceError::PatchConflictError(_) => StatusCode::CONFLICT,
+            ServiceError::ReadOnlyFieldError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ServiceError::PreconditionFailedError(_) => StatusCode::PRECONDITION_FAILED,
+            ServiceError::PreconditionRequiredError(_) => StatusCode::PRECONDITION_REQUIRED,
+            ServiceError::CustomMethodNotFoundError(_) => StatusCode::NOT_FOUND,
+            ServiceError::BatchAbortedError(_) => StatusCode::FAILED_DEPENDENCY,
             // 5xx
             ServiceError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::BlobStoreError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::PasswordHashError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::BlockingTaskError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::TokenSigningError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::OidcRequestError(_) => StatusCode::BAD_GATEWAY,
+            ServiceError::OidcProviderError(_) => StatusCode::BAD_GATEWAY,
         }
     }
 
@@ -55,6 +355,7 @@ impl ServiceError {
                 QueryRejection::FailedToDeserializeQueryString(_) => 40100,
                 _ => todo!(),
             },
+            ServiceError::InvalidCursorError(_) => 40101,
             ServiceError::PathExtractorRejection(err) => match err {
                 PathRejection::FailedToDeserializePathParams(_) => 40200,
                 PathRejection::MissingPathParams(_) => 40201,
@@ -68,6 +369,78 @@ impl ServiceError {
                 _ => todo!(),
             },
             ServiceError::TodoNotFoundError(_) => 40400,
+            ServiceError::CustomMethodNotFoundError(_) => 40401,
+            ServiceError::TrashedTodoNotFoundError(_) => 40402,
+            ServiceError::TagNotFoundError(_) => 40403,
+            ServiceError::ListNotFoundError(_) => 40404,
+            ServiceError::VersionNotFoundError(_, _) => 40405,
+            ServiceError::CommentNotFoundError(_, _) => 40406,
+            ServiceError::AttachmentNotFoundError(_, _) => 40407,
+            ServiceError::ApiKeyNotFoundError(_) => 40408,
+            ServiceError::ShareNotFoundError(_) => 40409,
+            ServiceError::WorkspaceNotFoundError(_) => 40410,
+            ServiceError::UnsupportedPatchMediaTypeError(_) => 40500,
+            ServiceError::InvalidPatchError(_) => 40501,
+            ServiceError::PatchConflictError(_) => 40502,
+            ServiceError::ReadOnlyFieldError(_) => 40503,
+            ServiceError::PreconditionFailedError(_) => 40600,
+            ServiceError::PreconditionRequiredError(_) => 40601,
+            ServiceError::BatchAbortedError(_) => 40700,
+            ServiceError::TodoTrashedError(_) => 40800,
+            ServiceError::TagNameConflictError(_) => 40900,
+            ServiceError::UnknownTagError(_) => 40901,
+            ServiceError::ListNotEmptyError(_) => 41000,
+            ServiceError::UnknownListError(_) => 41001,
+            ServiceError::ListOwnerRequiredError(_) => 41002,
+            ServiceError::ListEditorRequiredError(_) => 41003,
+            ServiceError::UnknownParentError(_) => 41100,
+            ServiceError::ParentCycleError(_, _) => 41101,
+            ServiceError::MoveNeighbourNotFoundError(_) => 41200,
+            ServiceError::InvalidMoveError(_) => 41201,
+            ServiceError::TodoNotRecurringError(_) => 41300,
+            ServiceError::RecurrenceError(_) => 41301,
+            ServiceError::MultipartExtractorRejection(err) => match err {
+                MultipartRejection::InvalidBoundary(_) => 41400,
+                _ => 41405,
+            },
+            ServiceError::MultipartError(_) => 41401,
+            ServiceError::MissingAttachmentFileError => 41402,
+            ServiceError::AttachmentTooLargeError(_) => 41403,
+            ServiceError::UnsupportedAttachmentTypeError(_) => 41404,
+            ServiceError::Unauthorized(reason) => match reason {
+                UnauthorizedReason::MissingToken => 41500,
+                UnauthorizedReason::InvalidCredentials => 41501,
+                UnauthorizedReason::InvalidToken => 41503,
+                UnauthorizedReason::ExpiredToken => 41504,
+                UnauthorizedReason::InvalidRefreshToken => 41505,
+                UnauthorizedReason::RefreshTokenReused => 41506,
+                UnauthorizedReason::InvalidApiKey => 41507,
+                UnauthorizedReason::ExpiredApiKey => 41508,
+                UnauthorizedReason::InvalidIdToken => 41510,
+                UnauthorizedReason::RevokedToken => 41511,
+            },
+            ServiceError::EmailTakenError(_) => 41502,
+            ServiceError::ReadOnlyApiKeyError => 41509,
+            ServiceError::OidcNotConfiguredError => 41600,
+            ServiceError::InvalidOidcStateError => 41601,
+            ServiceError::OidcLoginDeniedError(_) => 41602,
+            ServiceError::OidcMissingEmailError => 41603,
+            ServiceError::OidcEmailConflictError(_) => 41604,
+            ServiceError::Forbidden(_, role) => match role {
+                Role::Viewer => 41700,
+                Role::Editor => 41701,
+                Role::Owner => 41702,
+            },
+            ServiceError::CommentAuthorRequiredError(_, _) => 41703,
+            ServiceError::UnknownShareUserError(_) => 41800,
+            ServiceError::InvalidShareError(_) => 41801,
+            ServiceError::WorkspaceMismatchError(_) => 41900,
+            ServiceError::WorkspaceAdminRequiredError(_) => 41901,
+            ServiceError::WorkspaceSlugTakenError(_) => 41902,
+            ServiceError::UnknownMemberError(_) => 41903,
+            ServiceError::LastWorkspaceAdminError(_) => 41904,
+            ServiceError::RateLimitedError(_) => 42000,
+            ServiceError::VersionNotRevertibleError(_, _, _) => 42100,
 
             // 5xx
             ServiceError::Database(err) => match err {
@@ -92,6 +465,12 @@ impl ServiceError {
                 sea_orm::DbErr::RecordNotInserted => 50113,
                 sea_orm::DbErr::RecordNotUpdated => 50114,
             },
+            ServiceError::BlobStoreError(_) => 50200,
+            ServiceError::PasswordHashError(_) => 50300,
+            ServiceError::BlockingTaskError(_) => 50301,
+            ServiceError::TokenSigningError(_) => 50302,
+            ServiceError::OidcRequestError(_) => 50400,
+            ServiceError::OidcProviderError(_) => 50401,
         }
     }
 
@@ -100,15 +479,29 @@ impl ServiceError {
     }
 }
 
+impl From<&ServiceError> for ErrorResponse {
+    fn from(value: &ServiceError) -> Self {
+        Self {
+            code: value.get_internal_code(),
+            message: value.get_prompt_message(),
+        }
+    }
+}
+
 impl IntoResponse for ServiceError {
     fn into_response(self) -> Response {
-        (
-            self.get_status_code(),
-            ErrorResponse {
-                code: self.get_internal_code(),
-                message: self.get_prompt_message(),
-            },
-        )
-            .into_response()
+        let mut res = (self.get_status_code(), ErrorResponse::from(&self)).into_response();
+        // every 401 names the scheme to authenticate with, see RFC 9110 section 11.6.1
+        if let ServiceError::Unauthorized(reason) = &self {
+            res.headers_mut()
+                .insert(header::WWW_AUTHENTICATE, reason.challenge());
+        }
+        // see RFC 9110 section 10.2.3
+        if let ServiceError::RateLimitedError(limit) = &self {
+            res.headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(limit.reset));
+            res.headers_mut().extend(limit.headers());
+        }
+        res
     }
 }
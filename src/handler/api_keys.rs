@@ -0,0 +1,138 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use garde::Validate;
+use http::StatusCode;
+
+use entity::api_keys::ActiveModel as ApiKeysActiveModel;
+use entity::api_keys::Column as ApiKeysColumn;
+use entity::api_keys::Entity as ApiKeysEntity;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+use crate::auth::{new_api_key, CurrentUser, CurrentWorkspace};
+use crate::{
+    dto::{ApiKeyRequest, ApiKeyResponse, ApiKeysResponse, NewApiKeyResponse},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-keys",
+    responses(
+        (status = 200, description = "get the api keys of the user, newest first", body = [ApiKeysResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn get_api_keys(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<ApiKeysResponse> {
+    let api_keys = ApiKeysEntity::find()
+        .filter(ApiKeysColumn::UserId.eq(user.id))
+        .order_by_desc(ApiKeysColumn::Id)
+        .all(&*state.database)
+        .await?;
+
+    Ok(ApiKeysResponse {
+        api_keys: api_keys.into_iter().map(Into::into).collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = ApiKeyRequest,
+    path = "/api/v1/api-keys",
+    responses(
+        (status = 201, description = "create api key limited to the workspace of the request, the key itself is only part of this response", body = [NewApiKeyResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 404, description = "workspace not found", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn post_api_keys(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Json(payload), _): WithRejection<Json<ApiKeyRequest>, ServiceError>,
+) -> AppResult<(StatusCode, NewApiKeyResponse)> {
+    payload.validate(&())?;
+
+    let new_key = new_api_key();
+    let api_key = ApiKeysActiveModel {
+        user_id: ActiveValue::set(user.id),
+        workspace_id: ActiveValue::set(Some(workspace.id)),
+        name: ActiveValue::set(payload.name.trim().to_owned()),
+        prefix: ActiveValue::set(new_key.prefix),
+        salt: ActiveValue::set(new_key.salt),
+        key_hash: ActiveValue::set(new_key.key_hash),
+        scope: ActiveValue::set(payload.scope.into()),
+        expires_at: ActiveValue::set(payload.expires_at),
+        ..Default::default()
+    };
+    let res = api_key.insert(&*state.database).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        NewApiKeyResponse {
+            key: new_key.key,
+            api_key: res.into(),
+        },
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/api-keys/{id}",
+    responses(
+        (status = 200, description = "get api key by id", body = [ApiKeyResponse]),
+        (status = 404, description = "api key not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "api key database id"),
+    )
+)]
+pub async fn get_api_key_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<ApiKeyResponse> {
+    let res = ApiKeysEntity::find_by_id(id)
+        .filter(ApiKeysColumn::UserId.eq(user.id))
+        .one(&*state.database)
+        .await?
+        .ok_or(ServiceError::ApiKeyNotFoundError(id))?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/api-keys/{id}",
+    responses(
+        (status = 200, description = "revoke api key"),
+        (status = 204, description = "api key not found"),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "api key database id to revoke"),
+    )
+)]
+pub async fn delete_api_key_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let res = ApiKeysEntity::delete_many()
+        .filter(ApiKeysColumn::Id.eq(id))
+        .filter(ApiKeysColumn::UserId.eq(user.id))
+        .exec(&*state.database)
+        .await?;
+
+    match res.rows_affected {
+        0 => Ok(StatusCode::NO_CONTENT),
+        _ => Ok(StatusCode::OK),
+    }
+}
@@ -0,0 +1,470 @@
+use axum::body::{Body, Bytes};
+use axum::extract::{multipart::Field, Multipart, Path, State};
+use axum::response::{IntoResponse, Response};
+use axum_extra::extract::WithRejection;
+use http::{header, HeaderValue, StatusCode};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use entity::attachments::ActiveModel as AttachmentsActiveModel;
+use entity::attachments::Column as AttachmentsColumn;
+use entity::attachments::Entity as AttachmentsEntity;
+use entity::attachments::Model as AttachmentsModel;
+use sea_orm::sea_query::IntoCondition;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::blob::BlobStore;
+use crate::config::AttachmentsConfig;
+use crate::handler::todos::find_live_todo;
+use crate::{
+    dto::{AttachmentResponse, AttachmentsResponse, Role},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+// Enough of the start of a file to recognise its media type
+const SNIFF_LENGTH: usize = 512;
+
+// Signatures of the binary formats that are recognised, at the start of the file unless noted
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"II*\x00", "image/tiff"),
+    (b"MM\x00*", "image/tiff"),
+    (b"BM", "image/bmp"),
+    (b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+    (b"%PDF-", "application/pdf"),
+];
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/attachments",
+    responses(
+        (status = 200, description = "get the attachments of a todo, oldest first", body = [AttachmentsResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to get attachments for"),
+    )
+)]
+pub async fn get_todo_attachments(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<AttachmentsResponse> {
+    let txn = workspace.begin(&state).await?;
+
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let attachments = AttachmentsEntity::find()
+        .filter(AttachmentsColumn::TodoId.eq(id))
+        .order_by_asc(AttachmentsColumn::Id)
+        .all(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(AttachmentsResponse {
+        attachments: attachments.into_iter().map(Into::into).collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body(content = AttachmentUpload, content_type = "multipart/form-data"),
+    path = "/api/v1/todos/{id}/attachments",
+    responses(
+        (status = 201, description = "attach the file of the multipart field file to a todo", body = [AttachmentResponse]),
+        (status = 400, description = "invalid multipart body or missing field file", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 413, description = "file is larger than the configured max size", body = [ErrorResponse]),
+        (status = 415, description = "media type of the file is not allowed", body = [ErrorResponse]),
+        (status = 500, description = "database or blob store error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to attach the file to"),
+    )
+)]
+pub async fn post_todo_attachments(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(mut multipart, _): WithRejection<Multipart, ServiceError>,
+) -> AppResult<(StatusCode, AttachmentResponse)> {
+    // no transaction is held open while the file is uploading
+    let txn = workspace.begin(&state).await?;
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    txn.commit().await?;
+
+    let config = &state.config.attachments;
+    let mut field = loop {
+        match multipart.next_field().await? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => return Err(ServiceError::MissingAttachmentFileError),
+        }
+    };
+    let file_name = field.file_name().unwrap_or("attachment").to_owned();
+
+    let key = Uuid::new_v4().simple().to_string();
+    let (size, sha256, content_type) =
+        match store_blob(&*state.blobs, &key, &mut field, config).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                discard_blobs(&*state.blobs, &[key]).await;
+                return Err(err);
+            }
+        };
+
+    let attachment = AttachmentsActiveModel {
+        todo_id: ActiveValue::set(id),
+        workspace_id: ActiveValue::set(workspace.id),
+        file_name: ActiveValue::set(file_name),
+        content_type: ActiveValue::set(content_type),
+        size: ActiveValue::set(size),
+        sha256: ActiveValue::set(sha256),
+        storage_key: ActiveValue::set(key.clone()),
+        ..Default::default()
+    };
+    // the todo may have been purged while the file was uploading
+    let txn = workspace.begin(&state).await?;
+    let res = match attachment.insert(&txn).await {
+        Ok(res) => res,
+        Err(err) => {
+            discard_blobs(&*state.blobs, &[key]).await;
+            return Err(err.into());
+        }
+    };
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/attachments/{attachment_id}",
+    responses(
+        (status = 200, description = "get attachment metadata", body = [AttachmentResponse]),
+        (status = 404, description = "todo or attachment not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the attachment belongs to"),
+        ("attachment_id" = u64, Path, description = "attachment database id to get metadata for"),
+    )
+)]
+pub async fn get_todo_attachment_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, attachment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<AttachmentResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = find_attachment(&txn, workspace.id, user.id, id, attachment_id).await?;
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/attachments/{attachment_id}/content",
+    responses(
+        (status = 200, description = "download attachment contents with their stored media type",
+            content_type = "application/octet-stream",
+            headers(("content-disposition" = String, description = "stored file name"))),
+        (status = 404, description = "todo or attachment not found", body = [ErrorResponse]),
+        (status = 500, description = "database or blob store error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the attachment belongs to"),
+        ("attachment_id" = u64, Path, description = "attachment database id to download"),
+    )
+)]
+pub async fn get_todo_attachment_content(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, attachment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<Response> {
+    let txn = workspace.begin(&state).await?;
+    let attachment = find_attachment(&txn, workspace.id, user.id, id, attachment_id).await?;
+    txn.commit().await?;
+    let reader = state.blobs.open(&attachment.storage_key).await?;
+
+    let content_type = HeaderValue::from_str(&attachment.content_type)
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    let headers = [
+        (header::CONTENT_TYPE, content_type),
+        (header::CONTENT_LENGTH, HeaderValue::from(attachment.size)),
+        (
+            header::CONTENT_DISPOSITION,
+            content_disposition(&attachment.file_name),
+        ),
+    ];
+
+    Ok((headers, Body::from_stream(ReaderStream::new(reader))).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todos/{id}/attachments/{attachment_id}",
+    responses(
+        (status = 200, description = "delete attachment"),
+        (status = 204, description = "attachment not found"),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the attachment belongs to"),
+        ("attachment_id" = u64, Path, description = "attachment database id to delete"),
+    )
+)]
+pub async fn delete_todo_attachment_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, attachment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let condition = AttachmentsColumn::Id
+        .eq(attachment_id)
+        .and(AttachmentsColumn::TodoId.eq(id));
+    let keys = attachment_keys(&txn, condition.clone()).await?;
+    if keys.is_empty() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    AttachmentsEntity::delete_many()
+        .filter(condition)
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    discard_blobs(&*state.blobs, &keys).await;
+
+    Ok(StatusCode::OK)
+}
+
+// Attachments of trashed todos are kept but out of reach until the todo is restored
+async fn find_attachment(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    user_id: i64,
+    id: i64,
+    attachment_id: i64,
+) -> AppResult<AttachmentsModel> {
+    find_live_todo(db, workspace_id, user_id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    AttachmentsEntity::find_by_id(attachment_id)
+        .filter(AttachmentsColumn::WorkspaceId.eq(workspace_id))
+        .filter(AttachmentsColumn::TodoId.eq(id))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::AttachmentNotFoundError(id, attachment_id))
+}
+
+// Streams the field into a new blob, returning its size, hex encoded checksum and media type.
+// The Content-Type of the part is up to the client, so the media type checked against the allowed
+// ones is recognised from the first bytes before anything is stored.
+async fn store_blob(
+    blobs: &dyn BlobStore,
+    key: &str,
+    field: &mut Field<'_>,
+    config: &AttachmentsConfig,
+) -> AppResult<(i64, String, String)> {
+    let mut head = Vec::new();
+    while head.len() < SNIFF_LENGTH {
+        match field.chunk().await? {
+            Some(chunk) => head.extend_from_slice(&chunk),
+            None => break,
+        }
+        if head.len() as u64 > config.max_size {
+            return Err(ServiceError::AttachmentTooLargeError(config.max_size));
+        }
+    }
+    let content_type = sniff_content_type(&head[..head.len().min(SNIFF_LENGTH)]);
+    if !config.allows_content_type(content_type) {
+        return Err(ServiceError::UnsupportedAttachmentTypeError(
+            content_type.to_owned(),
+        ));
+    }
+
+    let mut writer = blobs.create(key).await?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+
+    let mut next = Some(Bytes::from(head));
+    while let Some(chunk) = next {
+        size += chunk.len() as u64;
+        if size > config.max_size {
+            return Err(ServiceError::AttachmentTooLargeError(config.max_size));
+        }
+        hasher.update(&chunk);
+        writer.write_all(&chunk).await?;
+        next = field.chunk().await?;
+    }
+    writer.shutdown().await?;
+
+    Ok((
+        size as i64,
+        format!("{:x}", hasher.finalize()),
+        content_type.to_owned(),
+    ))
+}
+
+// Media type of a file from its first bytes, anything neither a known binary format nor UTF-8
+// text without NUL bytes is opaque
+fn sniff_content_type(head: &[u8]) -> &'static str {
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| head.starts_with(signature))
+    {
+        return content_type;
+    }
+    if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        return "image/webp";
+    }
+
+    // the head may end in the middle of a character
+    let is_text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    };
+    match is_text && !head.contains(&0) {
+        true => "text/plain",
+        false => "application/octet-stream",
+    }
+}
+
+// Storage keys of the matching attachments, read before their rows go away
+pub(crate) async fn attachment_keys(
+    db: &impl ConnectionTrait,
+    condition: impl IntoCondition,
+) -> AppResult<Vec<String>> {
+    Ok(AttachmentsEntity::find()
+        .select_only()
+        .column(AttachmentsColumn::StorageKey)
+        .filter(condition)
+        .into_tuple()
+        .all(db)
+        .await?)
+}
+
+// Contents are removed once their rows are gone for good, a leftover blob is only wasted space
+pub(crate) async fn discard_blobs(blobs: &dyn BlobStore, keys: &[String]) {
+    for key in keys {
+        if let Err(err) = blobs.delete(key).await {
+            tracing::warn!("cannot delete attachment blob {}: {}", key, err);
+        }
+    }
+}
+
+// Plain ASCII filename for old clients along with the exact RFC 5987 encoded one
+fn content_disposition(file_name: &str) -> HeaderValue {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| match c {
+            ' '..='~' if c != '"' && c != '\\' => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (b as char).to_string(),
+            b'!' | b'#' | b'$' | b'&' | b'+' | b'-' | b'.' | b'^' | b'_' | b'`' | b'|' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+
+    HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    ))
+    .unwrap_or(HeaderValue::from_static("attachment"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_formats_are_recognised_by_their_signature() {
+        let files: &[(&[u8], &str)] = &[
+            (b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR", "image/png"),
+            (b"\xff\xd8\xff\xe0\x00\x10JFIF", "image/jpeg"),
+            (b"GIF89a\x01\x00\x01\x00", "image/gif"),
+            (b"II*\x00\x08\x00\x00\x00", "image/tiff"),
+            (b"RIFF\x24\x00\x00\x00WEBPVP8 ", "image/webp"),
+            (b"%PDF-1.7\n%\xe2\xe3\xcf\xd3", "application/pdf"),
+        ];
+        for (head, content_type) in files {
+            assert_eq!(sniff_content_type(head), *content_type);
+        }
+    }
+
+    #[test]
+    fn other_riff_files_are_opaque() {
+        assert_eq!(
+            sniff_content_type(b"RIFF\x24\x00\x00\x00WAVEfmt \x10\x00\x00\x00"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn utf8_without_nul_bytes_is_text() {
+        assert_eq!(sniff_content_type(b"groceries:\n- milk\n"), "text/plain");
+        assert_eq!(
+            sniff_content_type("caf\u{e9} \u{2615}".as_bytes()),
+            "text/plain"
+        );
+        assert_eq!(sniff_content_type(b""), "text/plain");
+    }
+
+    #[test]
+    fn text_cut_in_the_middle_of_a_character_is_text() {
+        let text = "na\u{ef}ve".as_bytes();
+        assert_eq!(sniff_content_type(&text[..3]), "text/plain");
+    }
+
+    #[test]
+    fn binary_data_is_opaque() {
+        assert_eq!(
+            sniff_content_type(b"text\x00with nul"),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            sniff_content_type(b"\xc3\x28 invalid"),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            sniff_content_type(b"\x7fELF\x02\x01\x01\x00"),
+            "application/octet-stream"
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use axum::extract::State;
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use chrono::{Duration, Utc};
+use garde::Validate;
+use http::{HeaderMap, StatusCode};
+use uuid::Uuid;
+
+use entity::refresh_tokens::ActiveModel as RefreshTokensActiveModel;
+use entity::refresh_tokens::Column as RefreshTokensColumn;
+use entity::refresh_tokens::Entity as RefreshTokensEntity;
+use entity::users::ActiveModel as UsersActiveModel;
+use entity::users::Column as UsersColumn;
+use entity::users::Entity as UsersEntity;
+use entity::users::Model as UsersModel;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QuerySelect, SqlErr, TransactionTrait,
+};
+
+use crate::auth::{hash_password, login_workspace, new_token, token_hash, verify_password};
+use crate::handler::workspaces::join_default_workspace;
+use crate::{
+    dto::{CredentialsRequest, RefreshRequest, TokenResponse, UserResponse},
+    error::{AppResult, ServiceError, UnauthorizedReason},
+    server::AppState,
+};
+
+#[utoipa::path(
+    post,
+    request_body = CredentialsRequest,
+    path = "/api/v1/auth/register",
+    responses(
+        (status = 201, description = "register user", body = [UserResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 409, description = "email is already registered", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    security(())
+)]
+pub async fn register(
+    State(state): State<AppState>,
+    WithRejection(Json(payload), _): WithRejection<Json<CredentialsRequest>, ServiceError>,
+) -> AppResult<(StatusCode, UserResponse)> {
+    payload.validate(&())?;
+
+    let email = payload.email.to_lowercase();
+    let password_hash = hash_password(payload.password).await?;
+
+    let txn = state.database.begin().await?;
+
+    let user = UsersActiveModel {
+        email: ActiveValue::set(email.clone()),
+        password_hash: ActiveValue::set(Some(password_hash)),
+        ..Default::default()
+    };
+    let res = user.insert(&txn).await.map_err(|err| match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => ServiceError::EmailTakenError(email),
+        _ => err.into(),
+    })?;
+    join_default_workspace(&state, &txn, res.id).await?;
+
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res.into()))
+}
+
+#[utoipa::path(
+    post,
+    request_body = CredentialsRequest,
+    path = "/api/v1/auth/login",
+    responses(
+        (status = 200, description = "log in and get an access token", body = [TokenResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 401, description = "unknown email or wrong password", body = [ErrorResponse]),
+        (status = 404, description = "user is no member of the workspace named", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    security(())
+)]
+pub async fn login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    WithRejection(Json(payload), _): WithRejection<Json<CredentialsRequest>, ServiceError>,
+) -> AppResult<TokenResponse> {
+    payload.validate(&())?;
+
+    let user = UsersEntity::find()
+        .filter(UsersColumn::Email.eq(payload.email.to_lowercase()))
+        .one(&*state.database)
+        .await?;
+    // accounts of the identity provider have no password to log in with
+    let user = user.and_then(|user| user.password_hash.clone().map(|hash| (user, hash)));
+    let (user, password_hash) = match user {
+        Some(user) => user,
+        None => {
+            // as slow as a wrong password, so response times do not tell which emails exist
+            hash_password(payload.password).await?;
+            return Err(ServiceError::Unauthorized(
+                UnauthorizedReason::InvalidCredentials,
+            ));
+        }
+    };
+    if !verify_password(payload.password, password_hash).await? {
+        return Err(ServiceError::Unauthorized(
+            UnauthorizedReason::InvalidCredentials,
+        ));
+    }
+
+    let workspace_id = login_workspace(&state, &*state.database, user.id, &headers).await?;
+    let family = Uuid::new_v4().simple().to_string();
+    issue_tokens(&state, &*state.database, user, family, workspace_id).await
+}
+
+#[utoipa::path(
+    post,
+    request_body = RefreshRequest,
+    path = "/api/v1/auth/refresh",
+    responses(
+        (status = 200, description = "trade a refresh token for a new access and refresh token", body = [TokenResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 401, description = "refresh token is unknown, expired, revoked or used before", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    security(())
+)]
+pub async fn refresh(
+    State(state): State<AppState>,
+    WithRejection(Json(payload), _): WithRejection<Json<RefreshRequest>, ServiceError>,
+) -> AppResult<TokenResponse> {
+    payload.validate(&())?;
+
+    let txn = state.database.begin().await?;
+
+    // locked so two refreshes racing with the same token cannot both succeed
+    let token = RefreshTokensEntity::find()
+        .filter(RefreshTokensColumn::TokenHash.eq(token_hash(&payload.refresh_token)))
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::Unauthorized(
+            UnauthorizedReason::InvalidRefreshToken,
+        ))?;
+
+    let now = Utc::now().naive_utc();
+    // a used token showing up again means it leaked, so the whole login is ended
+    if token.revoked_at.is_some() {
+        revoke_family(&txn, &token.family).await?;
+        txn.commit().await?;
+        return Err(ServiceError::Unauthorized(
+            UnauthorizedReason::RefreshTokenReused,
+        ));
+    }
+    if token.expires_at <= now {
+        return Err(ServiceError::Unauthorized(
+            UnauthorizedReason::InvalidRefreshToken,
+        ));
+    }
+
+    let user = UsersEntity::find_by_id(token.user_id)
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::Unauthorized(
+            UnauthorizedReason::InvalidRefreshToken,
+        ))?;
+
+    let (family, workspace_id) = (token.family.clone(), token.workspace_id);
+    let mut token: RefreshTokensActiveModel = token.into();
+    token.revoked_at = ActiveValue::set(Some(now));
+    token.update(&txn).await?;
+
+    let res = issue_tokens(&state, &txn, user, family, workspace_id).await?;
+
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+#[utoipa::path(
+    post,
+    request_body = RefreshRequest,
+    path = "/api/v1/auth/revoke",
+    responses(
+        (status = 200, description = "log out, revoking the refresh token along with every token it was refreshed from or into and the access tokens issued with them"),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    security(())
+)]
+pub async fn revoke(
+    State(state): State<AppState>,
+    WithRejection(Json(payload), _): WithRejection<Json<RefreshRequest>, ServiceError>,
+) -> AppResult<StatusCode> {
+    payload.validate(&())?;
+
+    let token = RefreshTokensEntity::find()
+        .filter(RefreshTokensColumn::TokenHash.eq(token_hash(&payload.refresh_token)))
+        .one(&*state.database)
+        .await?;
+    // unknown tokens are not reported, as in RFC 7009
+    if let Some(token) = token {
+        revoke_family(&*state.database, &token.family).await?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+// Signs an access token and stores a new refresh token of the login family, both limited to the
+// workspace the login was made in
+pub(crate) async fn issue_tokens(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    user: UsersModel,
+    family: String,
+    workspace_id: Option<i64>,
+) -> AppResult<TokenResponse> {
+    let access_token = state
+        .jwt
+        .issue(user.id, &user.email, workspace_id, &family)?;
+    let refresh_token = new_token();
+    let lifetime = state.config.auth.refresh_token_lifetime;
+    RefreshTokensActiveModel {
+        user_id: ActiveValue::set(user.id),
+        family: ActiveValue::set(family),
+        workspace_id: ActiveValue::set(workspace_id),
+        token_hash: ActiveValue::set(token_hash(&refresh_token)),
+        expires_at: ActiveValue::set(Utc::now().naive_utc() + Duration::seconds(lifetime)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer".to_owned(),
+        expires_in: state.jwt.lifetime(),
+        refresh_token,
+        user: user.into(),
+    })
+}
+
+async fn revoke_family(db: &impl ConnectionTrait, family: &str) -> AppResult<()> {
+    RefreshTokensEntity::update_many()
+        .col_expr(
+            RefreshTokensColumn::RevokedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(RefreshTokensColumn::Family.eq(family))
+        .filter(RefreshTokensColumn::RevokedAt.is_null())
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
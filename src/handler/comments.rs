@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use garde::Validate;
+use http::StatusCode;
+
+use entity::comments::ActiveModel as CommentsActiveModel;
+use entity::comments::Column as CommentsColumn;
+use entity::comments::Entity as CommentsEntity;
+use entity::comments::Model as CommentsModel;
+use entity::todos::Column as TodosColumn;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::handler::todos::{find_live_todo, touch_todos};
+use crate::{
+    dto::{
+        CommentRequest, CommentResponse, CommentsQuery, CommentsResponse, IdCursor, Role, Validated,
+    },
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/comments",
+    responses(
+        (status = 200, description = "get a page of comments on a todo, oldest first", body = [CommentsResponse]),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to get comments for"),
+        CommentsQuery,
+    )
+)]
+pub async fn get_todo_comments(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<CommentsQuery>>,
+        ServiceError,
+    >,
+) -> AppResult<CommentsResponse> {
+    let txn = workspace.begin(&state).await?;
+
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let pagination = &state.config.pagination;
+    let limit = query
+        .limit
+        .unwrap_or(pagination.default_page_size)
+        .min(pagination.max_page_size);
+
+    let mut select = CommentsEntity::find().filter(CommentsColumn::TodoId.eq(id));
+    if let Some(cursor) = &query.cursor {
+        let cursor = IdCursor::decode(cursor)?;
+        select = select.filter(CommentsColumn::Id.gt(cursor.id));
+    }
+    // one more than asked for tells whether there is a next page
+    let mut comments = select
+        .order_by_asc(CommentsColumn::Id)
+        .limit(limit + 1)
+        .all(&txn)
+        .await?;
+
+    let has_more = comments.len() as u64 > limit;
+    comments.truncate(limit as usize);
+    let next_cursor = comments
+        .last()
+        .filter(|_| has_more)
+        .map(|comment| IdCursor { id: comment.id }.encode());
+
+    txn.commit().await?;
+
+    Ok(CommentsResponse {
+        comments: comments.into_iter().map(Into::into).collect(),
+        next_cursor,
+        has_more,
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = CommentRequest,
+    path = "/api/v1/todos/{id}/comments",
+    responses(
+        (status = 201, description = "comment on a todo", body = [CommentResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to comment on"),
+    )
+)]
+pub async fn post_todo_comments(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Json(payload), _): WithRejection<Json<CommentRequest>, ServiceError>,
+) -> AppResult<(StatusCode, CommentResponse)> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let comment = CommentsActiveModel {
+        todo_id: ActiveValue::set(id),
+        author_id: ActiveValue::set(Some(user.id)),
+        workspace_id: ActiveValue::set(workspace.id),
+        body: ActiveValue::set(payload.body),
+        ..Default::default()
+    };
+    let res = comment.insert(&txn).await?;
+    // the comment count is part of the todo representation
+    touch_todos(&txn, TodosColumn::Id.eq(id)).await?;
+
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/comments/{comment_id}",
+    responses(
+        (status = 200, description = "get comment", body = [CommentResponse]),
+        (status = 404, description = "todo or comment not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the comment is on"),
+        ("comment_id" = u64, Path, description = "comment database id to get comment for"),
+    )
+)]
+pub async fn get_todo_comment_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, comment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<CommentResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = find_comment(&txn, workspace.id, user.id, id, comment_id, Role::Viewer).await?;
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    put,
+    request_body = CommentRequest,
+    path = "/api/v1/todos/{id}/comments/{comment_id}",
+    responses(
+        (status = 200, description = "edit comment", body = [CommentResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 403, description = "comment was written by another user", body = [ErrorResponse]),
+        (status = 404, description = "todo or comment not found", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the comment is on"),
+        ("comment_id" = u64, Path, description = "comment database id to edit"),
+    )
+)]
+pub async fn put_todo_comment_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, comment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+    WithRejection(Json(payload), _): WithRejection<Json<CommentRequest>, ServiceError>,
+) -> AppResult<CommentResponse> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    let comment = find_comment(&txn, workspace.id, user.id, id, comment_id, Role::Editor).await?;
+    if comment.author_id != Some(user.id) {
+        return Err(ServiceError::CommentAuthorRequiredError(id, comment_id));
+    }
+
+    let mut comment: CommentsActiveModel = comment.into();
+    comment.body = ActiveValue::set(payload.body);
+    comment.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    let res = comment.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/todos/{id}/comments/{comment_id}",
+    responses(
+        (status = 200, description = "delete comment"),
+        (status = 204, description = "comment not found"),
+        (status = 403, description = "comment was written by another user and the todo is theirs", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id the comment is on"),
+        ("comment_id" = u64, Path, description = "comment database id to delete"),
+    )
+)]
+pub async fn delete_todo_comment_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path((id, comment_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let comment = match CommentsEntity::find_by_id(comment_id)
+        .filter(CommentsColumn::TodoId.eq(id))
+        .one(&txn)
+        .await?
+    {
+        Some(comment) => comment,
+        None => return Ok(StatusCode::NO_CONTENT),
+    };
+    // the owner of the todo moderates the comments on it
+    if comment.author_id != Some(user.id) && todo.owner_id != Some(user.id) {
+        return Err(ServiceError::CommentAuthorRequiredError(id, comment_id));
+    }
+
+    CommentsEntity::delete_by_id(comment_id).exec(&txn).await?;
+    touch_todos(&txn, TodosColumn::Id.eq(id)).await?;
+
+    txn.commit().await?;
+
+    Ok(StatusCode::OK)
+}
+
+// Comments of trashed todos are kept but out of reach until the todo is restored
+async fn find_comment(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    user_id: i64,
+    id: i64,
+    comment_id: i64,
+    role: Role,
+) -> AppResult<CommentsModel> {
+    find_live_todo(db, workspace_id, user_id, id, role)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    CommentsEntity::find_by_id(comment_id)
+        .filter(CommentsColumn::WorkspaceId.eq(workspace_id))
+        .filter(CommentsColumn::TodoId.eq(id))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::CommentNotFoundError(id, comment_id))
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CommentCountRow {
+    todo_id: i64,
+    count: i64,
+}
+
+pub(crate) async fn comment_counts(
+    db: &impl ConnectionTrait,
+    ids: &[i64],
+) -> AppResult<HashMap<i64, i64>> {
+    let counts = CommentsEntity::find()
+        .select_only()
+        .column(CommentsColumn::TodoId)
+        .column_as(Expr::col(CommentsColumn::Id).count(), "count")
+        .filter(CommentsColumn::TodoId.is_in(ids.iter().copied()))
+        .group_by(CommentsColumn::TodoId)
+        .into_model::<CommentCountRow>()
+        .all(db)
+        .await?;
+
+    Ok(counts
+        .into_iter()
+        .map(|row| (row.todo_id, row.count))
+        .collect())
+}
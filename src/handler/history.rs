@@ -0,0 +1,217 @@
+use axum::extract::{Path, Query, State};
+use axum_extra::extract::WithRejection;
+use axum_extra::headers::HeaderMapExt;
+use garde::Validate;
+use http::HeaderMap;
+use serde_json::Value;
+
+use entity::sea_orm_active_enums::TodoEventKind;
+use entity::todo_events::ActiveModel as TodoEventsActiveModel;
+use entity::todo_events::Column as TodoEventsColumn;
+use entity::todo_events::Entity as TodoEventsEntity;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect,
+};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::handler::todos::{check_if_match, find_live_todo, replace_todo, todo_response};
+use crate::{
+    dto::{
+        Audit, CustomMethodTarget, HistoryQuery, IdCursor, RevertQuery, Role, TodoHistoryResponse,
+        TodoResponse, UpdateTodoRequest, Validated,
+    },
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/history",
+    responses(
+        (status = 200, description = "get a page of changes made to a todo, newest first", body = [TodoHistoryResponse]),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to get the history of, purged todos keep theirs"),
+        HistoryQuery,
+    )
+)]
+pub async fn get_todo_history(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<HistoryQuery>>,
+        ServiceError,
+    >,
+) -> AppResult<TodoHistoryResponse> {
+    let pagination = &state.config.pagination;
+    let limit = query
+        .limit
+        .unwrap_or(pagination.default_page_size)
+        .min(pagination.max_page_size);
+
+    let txn = workspace.begin(&state).await?;
+
+    // users a live todo is shared with see its whole history, owners see it even once purged
+    let shared = find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .is_some();
+    let mut select = TodoEventsEntity::find()
+        .filter(TodoEventsColumn::WorkspaceId.eq(workspace.id))
+        .filter(TodoEventsColumn::TodoId.eq(id));
+    if !shared {
+        select = select.filter(TodoEventsColumn::OwnerId.eq(user.id));
+    }
+    if let Some(cursor) = &query.cursor {
+        let cursor = IdCursor::decode(cursor)?;
+        select = select.filter(TodoEventsColumn::Id.lt(cursor.id));
+    }
+    // one more than asked for tells whether there is a next page
+    let mut events = select
+        .order_by_desc(TodoEventsColumn::Id)
+        .limit(limit + 1)
+        .all(&txn)
+        .await?;
+
+    // todos created before the history existed have none
+    if events.is_empty()
+        && query.cursor.is_none()
+        && !shared
+        && TodosEntity::find_by_id(id)
+            .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+            .filter(TodosColumn::OwnerId.eq(user.id))
+            .one(&txn)
+            .await?
+            .is_none()
+    {
+        return Err(ServiceError::TodoNotFoundError(id));
+    }
+
+    let has_more = events.len() as u64 > limit;
+    events.truncate(limit as usize);
+    let next_cursor = events
+        .last()
+        .filter(|_| has_more)
+        .map(|event| IdCursor { id: event.id }.encode());
+
+    txn.commit().await?;
+
+    Ok(TodoHistoryResponse {
+        events: events.into_iter().map(Into::into).collect(),
+        next_cursor,
+        has_more,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/todos/{id}:revert",
+    responses(
+        (status = 200, description = "restore the fields of a previous version as a new change", body = [TodoResponse]),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "todo or version not found", body = [ErrorResponse]),
+        (status = 412, description = "todo was changed since it was read, it does not match If-Match", body = [ErrorResponse]),
+        (status = 422, description = "tags, list or parent of the version no longer exist, or the version is no longer a valid todo", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to revert"),
+        RevertQuery,
+    )
+)]
+pub async fn revert_todo_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(target), _): WithRejection<Path<CustomMethodTarget>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<RevertQuery>>,
+        ServiceError,
+    >,
+    headers: HeaderMap,
+    audit: Audit,
+) -> AppResult<TodoResponse> {
+    let id = target.id;
+    let txn = workspace.begin(&state).await?;
+
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    check_if_match(&state, headers.typed_get(), id, Some(&todo))?;
+
+    // versions bumped by changes to tags, lists or subtasks have no snapshot of their own
+    let snapshot = TodoEventsEntity::find()
+        .filter(TodoEventsColumn::WorkspaceId.eq(workspace.id))
+        .filter(TodoEventsColumn::TodoId.eq(id))
+        .filter(TodoEventsColumn::Version.eq(query.to_version))
+        .filter(TodoEventsColumn::After.is_not_null())
+        .order_by_desc(TodoEventsColumn::Id)
+        .one(&txn)
+        .await?
+        .and_then(|event| event.after)
+        .ok_or(ServiceError::VersionNotFoundError(id, query.to_version))?;
+
+    // the snapshot is a todo as returned by the API, whose editable fields form a replacement,
+    // unless it was recorded before they changed
+    let not_revertible =
+        |reason: String| ServiceError::VersionNotRevertibleError(id, query.to_version, reason);
+    let payload: UpdateTodoRequest =
+        serde_json::from_value(snapshot).map_err(|err| not_revertible(err.to_string()))?;
+    payload
+        .validate(&())
+        .map_err(|report| not_revertible(report.to_string()))?;
+
+    let res = replace_todo(&state, &txn, &audit, TodoEventKind::Reverted, todo, payload).await?;
+    let res = todo_response(&txn, res).await?;
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+// Records a change in the same transaction as the change itself
+pub(crate) async fn record_event(
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    kind: TodoEventKind,
+    before: Option<&TodoResponse>,
+    after: Option<&TodoResponse>,
+) -> AppResult<()> {
+    let (id, version, owner_id, workspace_id) = match after.or(before) {
+        Some(todo) => (todo.id, todo.version, todo.owner_id, todo.workspace_id),
+        None => return Ok(()),
+    };
+
+    // serializing a response never fails
+    let before = before.map(|todo| serde_json::to_value(todo).unwrap_or_default());
+    let after = after.map(|todo| serde_json::to_value(todo).unwrap_or_default());
+    let diff = json_patch::diff(
+        before.as_ref().unwrap_or(&Value::Null),
+        after.as_ref().unwrap_or(&Value::Null),
+    );
+
+    TodoEventsActiveModel {
+        todo_id: ActiveValue::set(id),
+        version: ActiveValue::set(version),
+        kind: ActiveValue::set(kind),
+        actor: ActiveValue::set(audit.actor.clone()),
+        request_id: ActiveValue::set(audit.request_id.clone()),
+        before: ActiveValue::set(before),
+        after: ActiveValue::set(after),
+        diff: ActiveValue::set(serde_json::to_value(diff).unwrap_or_default()),
+        owner_id: ActiveValue::set(owner_id),
+        workspace_id: ActiveValue::set(workspace_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,423 @@
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use garde::Validate;
+use http::{HeaderMap, StatusCode};
+
+use entity::lists::ActiveModel as ListsActiveModel;
+use entity::lists::Column as ListsColumn;
+use entity::lists::Entity as ListsEntity;
+use entity::lists::Model as ListsModel;
+use entity::sea_orm_active_enums::TodoEventKind;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+
+use crate::auth::{list_role, todo_role, CurrentUser, CurrentWorkspace};
+use crate::handler::history::record_event;
+use crate::handler::todos::{
+    create_todo, list_todos, live_todos, todo_response, touch_todos, trash_todo, update_todo,
+};
+use crate::{
+    dto::{
+        Audit, Conditional, DeleteListQuery, GetTodosQuery, ListDeletePolicy, ListRequest,
+        ListResponse, ListsResponse, NewTodoRequest, Role, TodoResponse, TodosResponse, Validated,
+    },
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/lists",
+    responses(
+        (status = 200, description = "get all lists sorted by name", body = [ListsResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn get_lists(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+) -> AppResult<ListsResponse> {
+    let txn = workspace.begin(&state).await?;
+
+    let res = ListsEntity::find()
+        .filter(ListsColumn::WorkspaceId.eq(workspace.id))
+        .order_by_asc(ListsColumn::Name)
+        .order_by_asc(ListsColumn::Id)
+        .all(&txn)
+        .await?;
+    let lists = list_responses(&txn, workspace.id, user.id, res).await?;
+
+    txn.commit().await?;
+
+    Ok(ListsResponse { lists })
+}
+
+#[utoipa::path(
+    post,
+    request_body = ListRequest,
+    path = "/api/v1/lists",
+    responses(
+        (status = 201, description = "create list", body = [ListResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn post_lists(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Json(payload), _): WithRejection<Json<ListRequest>, ServiceError>,
+) -> AppResult<(StatusCode, ListResponse)> {
+    payload.validate(&())?;
+
+    let list = ListsActiveModel {
+        workspace_id: ActiveValue::set(workspace.id),
+        owner_id: ActiveValue::set(Some(user.id)),
+        name: ActiveValue::set(payload.name),
+        description: ActiveValue::set(payload.description),
+        ..Default::default()
+    };
+
+    let txn = workspace.begin(&state).await?;
+    let res = list.insert(&txn).await?;
+    let res = list_response(&txn, workspace.id, user.id, res).await?;
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/lists/{list_id}",
+    responses(
+        (status = 200, description = "get list", body = [ListResponse]),
+        (status = 404, description = "list not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("list_id" = u64, Path, description = "list database id to get list for"),
+    )
+)]
+pub async fn get_list_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(list_id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<ListResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = find_list(&txn, workspace.id, list_id).await?;
+    let res = list_response(&txn, workspace.id, user.id, res).await?;
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+#[utoipa::path(
+    put,
+    request_body = ListRequest,
+    path = "/api/v1/lists/{list_id}",
+    responses(
+        (status = 200, description = "replace list", body = [ListResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 403, description = "user neither owns the list, edits it through a share nor is an admin of the workspace", body = [ErrorResponse]),
+        (status = 404, description = "list not found", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("list_id" = u64, Path, description = "list database id to replace"),
+    )
+)]
+pub async fn put_list_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(list_id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Json(payload), _): WithRejection<Json<ListRequest>, ServiceError>,
+) -> AppResult<ListResponse> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    let list = find_list(&txn, workspace.id, list_id).await?;
+    if list_role(&txn, user.id, &list).await? < Some(Role::Editor) {
+        return Err(ServiceError::ListEditorRequiredError(list_id));
+    }
+
+    let mut list: ListsActiveModel = list.into();
+    list.name = ActiveValue::set(payload.name);
+    list.description = ActiveValue::set(payload.description);
+    list.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    let res = list.update(&txn).await?;
+    let res = list_response(&txn, workspace.id, user.id, res).await?;
+
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/lists/{list_id}",
+    responses(
+        (status = 200, description = "delete list, todos that are not trashed with it are detached from it"),
+        (status = 204, description = "list not found"),
+        (status = 403, description = "user neither owns the list nor is an admin of the workspace", body = [ErrorResponse]),
+        (status = 409, description = "list still has todos and the policy is block", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("list_id" = u64, Path, description = "list database id to delete"),
+        DeleteListQuery,
+    )
+)]
+pub async fn delete_list_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(list_id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(query), _): WithRejection<Query<DeleteListQuery>, ServiceError>,
+    audit: Audit,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    let list = match ListsEntity::find_by_id(list_id)
+        .filter(ListsColumn::WorkspaceId.eq(workspace.id))
+        .one(&txn)
+        .await?
+    {
+        Some(list) => list,
+        None => return Ok(StatusCode::NO_CONTENT),
+    };
+    if list_role(&txn, user.id, &list).await? < Some(Role::Owner) {
+        return Err(ServiceError::ListOwnerRequiredError(list_id));
+    }
+
+    match query.on_delete.unwrap_or_default() {
+        ListDeletePolicy::Block => {
+            // todos of other users block it as well, even those the user cannot see
+            let todos = TodosEntity::find()
+                .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+                .filter(TodosColumn::ListId.eq(list_id))
+                .filter(TodosColumn::DeletedAt.is_null());
+            if todos.count(&txn).await? > 0 {
+                return Err(ServiceError::ListNotEmptyError(list_id));
+            }
+        }
+        ListDeletePolicy::Cascade => {
+            // only the todos the user can edit are trashed, the others are detached
+            let todos = live_todos(workspace.id, user.id).filter(TodosColumn::ListId.eq(list_id));
+            let mut parents = Vec::new();
+            for todo in todos.all(&txn).await? {
+                if todo_role(&txn, user.id, &todo).await? < Some(Role::Editor) {
+                    continue;
+                }
+                parents.extend(todo.parent_id);
+                trash_todo(&txn, &audit, todo).await?;
+            }
+            touch_todos(&txn, TodosColumn::Id.is_in(parents)).await?;
+        }
+    }
+    // detached here rather than by the foreign key so that their version changes and their
+    // history records it too
+    let todos = TodosEntity::find()
+        .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+        .filter(TodosColumn::ListId.eq(list_id))
+        .all(&txn)
+        .await?;
+    let now = Utc::now().naive_utc();
+    for todo in todos {
+        let before = todo_response(&txn, todo.clone()).await?;
+        let res = update_todo(&txn, todo, |todo| {
+            todo.list_id = ActiveValue::set(None);
+            todo.updated_at = ActiveValue::set(now);
+        })
+        .await?;
+        let after = todo_response(&txn, res).await?;
+        record_event(
+            &txn,
+            &audit,
+            TodoEventKind::Updated,
+            Some(&before),
+            Some(&after),
+        )
+        .await?;
+    }
+    ListsEntity::delete_by_id(list_id).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/lists/{list_id}/todos",
+    responses(
+        (status = 200, description = "get a page of filtered and sorted todos of the list", body = [TodosResponse],
+            headers(("etag" = String, description = "hash of the page"), ("last-modified" = String))),
+        (status = 304, description = "page matches If-None-Match or is unchanged since If-Modified-Since"),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "list not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("list_id" = u64, Path, description = "list database id to get todos for"),
+        GetTodosQuery,
+    )
+)]
+pub async fn get_list_todos(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(list_id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<GetTodosQuery>>,
+        ServiceError,
+    >,
+    headers: HeaderMap,
+) -> AppResult<Conditional<TodosResponse>> {
+    let txn = workspace.begin(&state).await?;
+
+    find_list(&txn, workspace.id, list_id).await?;
+
+    let select = live_todos(workspace.id, user.id).filter(TodosColumn::ListId.eq(list_id));
+    let todos = list_todos(&state, &txn, select, query).await?;
+
+    txn.commit().await?;
+
+    Ok(Conditional::new(&headers, todos))
+}
+
+#[utoipa::path(
+    post,
+    request_body = NewTodoRequest,
+    path = "/api/v1/lists/{list_id}/todos",
+    responses(
+        (status = 201, description = "create todo in the list", body = [TodoResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 404, description = "list not found", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("list_id" = u64, Path, description = "list database id to create the todo in"),
+    )
+)]
+pub async fn post_list_todos(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(list_id), _): WithRejection<Path<i64>, ServiceError>,
+    audit: Audit,
+    WithRejection(Json(mut payload), _): WithRejection<Json<NewTodoRequest>, ServiceError>,
+) -> AppResult<(StatusCode, TodoResponse)> {
+    let txn = workspace.begin(&state).await?;
+
+    find_list(&txn, workspace.id, list_id).await?;
+    payload.list_id = Some(list_id);
+    let res = create_todo(&state, &txn, &audit, workspace.id, user.id, payload).await?;
+    let res = todo_response(&txn, res).await?;
+
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res))
+}
+
+async fn find_list(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    list_id: i64,
+) -> AppResult<ListsModel> {
+    ListsEntity::find_by_id(list_id)
+        .filter(ListsColumn::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?
+        .ok_or(ServiceError::ListNotFoundError(list_id))
+}
+
+// A todo may only reference an existing list of its workspace, the foreign key alone would
+// surface as a 500
+pub(crate) async fn check_list_exists(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    list_id: Option<i64>,
+) -> AppResult<()> {
+    match list_id {
+        Some(list_id) => match find_list(db, workspace_id, list_id).await {
+            Ok(_) => Ok(()),
+            Err(ServiceError::ListNotFoundError(_)) => Err(ServiceError::UnknownListError(list_id)),
+            Err(err) => Err(err),
+        },
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct ListCountRow {
+    list_id: i64,
+    complated: bool,
+    count: i64,
+}
+
+async fn list_response(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    owner_id: i64,
+    list: ListsModel,
+) -> AppResult<ListResponse> {
+    let mut res = list_responses(db, workspace_id, owner_id, vec![list]).await?;
+
+    // one list in, one response out
+    Ok(res.remove(0))
+}
+
+// Counts only cover the todos of the user, who cannot see those of others
+async fn list_responses(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    owner_id: i64,
+    lists: Vec<ListsModel>,
+) -> AppResult<Vec<ListResponse>> {
+    let counts = live_todos(workspace_id, owner_id)
+        .select_only()
+        .column(TodosColumn::ListId)
+        .column(TodosColumn::Complated)
+        .column_as(Expr::col(TodosColumn::Id).count(), "count")
+        .filter(TodosColumn::ListId.is_in(lists.iter().map(|list| list.id)))
+        .group_by(TodosColumn::ListId)
+        .group_by(TodosColumn::Complated)
+        .into_model::<ListCountRow>()
+        .all(db)
+        .await?;
+
+    let count = |list_id: i64, complated: bool| {
+        counts
+            .iter()
+            .find(|row| row.list_id == list_id && row.complated == complated)
+            .map_or(0, |row| row.count)
+    };
+
+    Ok(lists
+        .into_iter()
+        .map(|list| ListResponse {
+            open_count: count(list.id, false),
+            completed_count: count(list.id, true),
+            id: list.id,
+            name: list.name,
+            description: list.description,
+            owner_id: list.owner_id,
+            created_at: list.created_at,
+            updated_at: list.updated_at,
+        })
+        .collect())
+}
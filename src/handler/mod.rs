@@ -1,3 +1,17 @@
+pub mod api_keys;
+pub mod attachments;
+pub mod auth;
+pub mod comments;
+pub mod history;
+pub mod lists;
+pub mod oidc;
 pub mod openapi;
+pub mod ordering;
+pub mod recurrence;
 pub mod server;
+pub mod shares;
+pub mod subtasks;
+pub mod tags;
 pub mod todos;
+pub mod trash;
+pub mod workspaces;
@@ -0,0 +1,190 @@
+use axum::extract::{Query, State};
+use axum::response::Redirect;
+use axum_extra::extract::WithRejection;
+use chrono::{Duration, Utc};
+use garde::Validate;
+use http::HeaderMap;
+use uuid::Uuid;
+
+use entity::oidc_logins::ActiveModel as OidcLoginsActiveModel;
+use entity::oidc_logins::Column as OidcLoginsColumn;
+use entity::oidc_logins::Entity as OidcLoginsEntity;
+use entity::users::ActiveModel as UsersActiveModel;
+use entity::users::Column as UsersColumn;
+use entity::users::Entity as UsersEntity;
+use entity::users::Model as UsersModel;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, SqlErr,
+    TransactionTrait,
+};
+
+use crate::auth::{code_challenge, login_workspace, new_token, IdTokenClaims};
+use crate::handler::{auth::issue_tokens, workspaces::join_default_workspace};
+use crate::{
+    dto::{OidcCallbackQuery, TokenResponse},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/oidc/authorize",
+    responses(
+        (status = 303, description = "start a login at the identity provider",
+            headers(("location" = String, description = "authorization endpoint of the provider"))),
+        (status = 404, description = "single sign-on is not configured", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+        (status = 502, description = "identity provider cannot be reached", body = [ErrorResponse]),
+    ),
+    security(())
+)]
+pub async fn authorize(State(state): State<AppState>) -> AppResult<Redirect> {
+    let oidc = state
+        .oidc
+        .as_ref()
+        .ok_or(ServiceError::OidcNotConfiguredError)?;
+
+    let now = Utc::now().naive_utc();
+    // logins given up on are cleaned up along the way
+    OidcLoginsEntity::delete_many()
+        .filter(OidcLoginsColumn::ExpiresAt.lte(now))
+        .exec(&*state.database)
+        .await?;
+
+    let login_state = new_token();
+    let code_verifier = new_token();
+    let nonce = new_token();
+    let url = oidc
+        .authorization_url(&login_state, &code_challenge(&code_verifier), &nonce)
+        .await?;
+
+    OidcLoginsActiveModel {
+        state: ActiveValue::set(login_state),
+        code_verifier: ActiveValue::set(code_verifier),
+        nonce: ActiveValue::set(nonce),
+        expires_at: ActiveValue::set(now + Duration::seconds(oidc.login_lifetime())),
+        ..Default::default()
+    }
+    .insert(&*state.database)
+    .await?;
+
+    Ok(Redirect::to(&url))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/oidc/callback",
+    responses(
+        (status = 200, description = "complete a login at the identity provider, creating the account on first login", body = [TokenResponse]),
+        (status = 400, description = "invalid request, or unknown or expired state", body = [ErrorResponse]),
+        (status = 401, description = "invalid id token", body = [ErrorResponse]),
+        (status = 403, description = "identity provider denied the login", body = [ErrorResponse]),
+        (status = 404, description = "single sign-on is not configured, or the user is no member of the workspace named", body = [ErrorResponse]),
+        (status = 409, description = "email belongs to another account", body = [ErrorResponse]),
+        (status = 422, description = "identity provider did not share an email", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+        (status = 502, description = "identity provider cannot be reached", body = [ErrorResponse]),
+    ),
+    params(OidcCallbackQuery),
+    security(())
+)]
+pub async fn callback(
+    State(state): State<AppState>,
+    WithRejection(Query(query), _): WithRejection<Query<OidcCallbackQuery>, ServiceError>,
+    headers: HeaderMap,
+) -> AppResult<TokenResponse> {
+    query.validate(&())?;
+    let oidc = state
+        .oidc
+        .as_ref()
+        .ok_or(ServiceError::OidcNotConfiguredError)?;
+
+    // taken before anything else, so a state cannot be replayed whatever the outcome
+    let login = OidcLoginsEntity::find()
+        .filter(OidcLoginsColumn::State.eq(&query.state))
+        .one(&*state.database)
+        .await?;
+    let login = match login {
+        Some(login) => login,
+        None => return Err(ServiceError::InvalidOidcStateError),
+    };
+    let res = OidcLoginsEntity::delete_by_id(login.id)
+        .exec(&*state.database)
+        .await?;
+    if res.rows_affected == 0 || login.expires_at <= Utc::now().naive_utc() {
+        return Err(ServiceError::InvalidOidcStateError);
+    }
+
+    let code = match (query.code, query.error) {
+        (_, Some(error)) => return Err(ServiceError::OidcLoginDeniedError(error)),
+        (Some(code), None) => code,
+        (None, None) => return Err(ServiceError::InvalidOidcStateError),
+    };
+    let id_token = oidc.exchange_code(&code, &login.code_verifier).await?;
+    let claims = oidc.verify_id_token(&id_token, &login.nonce).await?;
+
+    let txn = state.database.begin().await?;
+    let user = provision_user(&state, &txn, claims).await?;
+    let family = Uuid::new_v4().simple().to_string();
+    let workspace_id = login_workspace(&state, &txn, user.id, &headers).await?;
+    let res = issue_tokens(&state, &txn, user, family, workspace_id).await?;
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+// Finds the account of the identity, linking an account of the same verified email or creating
+// one on first login
+async fn provision_user(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    claims: IdTokenClaims,
+) -> AppResult<UsersModel> {
+    let user = UsersEntity::find()
+        .filter(UsersColumn::OidcIssuer.eq(&claims.iss))
+        .filter(UsersColumn::OidcSubject.eq(&claims.sub))
+        .one(db)
+        .await?;
+    if let Some(user) = user {
+        return Ok(user);
+    }
+
+    let email = claims
+        .email
+        .ok_or(ServiceError::OidcMissingEmailError)?
+        .to_lowercase();
+    let user = UsersEntity::find()
+        .filter(UsersColumn::Email.eq(&email))
+        .one(db)
+        .await?;
+    // an unverified email could be anyone's, so it never takes over an existing account
+    match user {
+        Some(user) if claims.email_verified == Some(true) && user.oidc_subject.is_none() => {
+            let mut user: UsersActiveModel = user.into();
+            user.oidc_issuer = ActiveValue::set(Some(claims.iss));
+            user.oidc_subject = ActiveValue::set(Some(claims.sub));
+            user.updated_at = ActiveValue::set(Utc::now().naive_utc());
+            Ok(user.update(db).await?)
+        }
+        Some(_) => Err(ServiceError::OidcEmailConflictError(email)),
+        None => {
+            let user = UsersActiveModel {
+                email: ActiveValue::set(email.clone()),
+                password_hash: ActiveValue::set(None),
+                oidc_issuer: ActiveValue::set(Some(claims.iss)),
+                oidc_subject: ActiveValue::set(Some(claims.sub)),
+                ..Default::default()
+            }
+            .insert(db)
+            .await
+            .map_err(|err| match err.sql_err() {
+                Some(SqlErr::UniqueConstraintViolation(_)) => {
+                    ServiceError::OidcEmailConflictError(email)
+                }
+                _ => err.into(),
+            })?;
+            join_default_workspace(state, db, user.id).await?;
+            Ok(user)
+        }
+    }
+}
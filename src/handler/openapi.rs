@@ -1,5 +1,8 @@
 use crate::dto::*;
-use utoipa::OpenApi;
+use utoipa::{
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    Modify, OpenApi,
+};
 
 #[derive(OpenApi)]
 #[openapi(
@@ -7,26 +10,169 @@ use utoipa::OpenApi;
         // server
         crate::handler::server::health,
         crate::handler::server::state,
+        // auth
+        crate::handler::auth::register,
+        crate::handler::auth::login,
+        crate::handler::auth::refresh,
+        crate::handler::auth::revoke,
+        crate::handler::oidc::authorize,
+        crate::handler::oidc::callback,
+        // api keys
+        crate::handler::api_keys::get_api_keys,
+        crate::handler::api_keys::post_api_keys,
+        crate::handler::api_keys::get_api_key_by_id,
+        crate::handler::api_keys::delete_api_key_by_id,
         // todos
         crate::handler::todos::get_todos,
         crate::handler::todos::post_todos,
+        crate::handler::todos::search_todos,
+        crate::handler::todos::batch_todos,
         crate::handler::todos::get_todo_by_id,
         crate::handler::todos::put_todo_by_id,
+        crate::handler::todos::patch_todo_by_id,
         crate::handler::todos::delete_todo_by_id,
+        crate::handler::todos::restore_todo_by_id,
+        crate::handler::ordering::move_todo_by_id,
+        crate::handler::recurrence::get_todo_occurrences,
+        crate::handler::history::get_todo_history,
+        crate::handler::history::revert_todo_by_id,
+        crate::handler::subtasks::get_todo_children,
+        crate::handler::subtasks::get_todo_subtree,
+        // trash
+        crate::handler::trash::get_trash,
+        crate::handler::trash::purge_trash,
+        crate::handler::trash::purge_trashed_todo_by_id,
+        // tags
+        crate::handler::tags::get_tags,
+        crate::handler::tags::post_tags,
+        crate::handler::tags::get_tag_by_id,
+        crate::handler::tags::put_tag_by_id,
+        crate::handler::tags::delete_tag_by_id,
+        // lists
+        crate::handler::lists::get_lists,
+        crate::handler::lists::post_lists,
+        crate::handler::lists::get_list_by_id,
+        crate::handler::lists::put_list_by_id,
+        crate::handler::lists::delete_list_by_id,
+        crate::handler::lists::get_list_todos,
+        crate::handler::lists::post_list_todos,
+        // comments
+        crate::handler::comments::get_todo_comments,
+        crate::handler::comments::post_todo_comments,
+        crate::handler::comments::get_todo_comment_by_id,
+        crate::handler::comments::put_todo_comment_by_id,
+        crate::handler::comments::delete_todo_comment_by_id,
+        // attachments
+        crate::handler::attachments::get_todo_attachments,
+        crate::handler::attachments::post_todo_attachments,
+        crate::handler::attachments::get_todo_attachment_by_id,
+        crate::handler::attachments::get_todo_attachment_content,
+        crate::handler::attachments::delete_todo_attachment_by_id,
+        // shares
+        crate::handler::shares::get_shares,
+        crate::handler::shares::post_shares,
+        crate::handler::shares::get_share_by_id,
+        crate::handler::shares::delete_share_by_id,
+        // workspaces
+        crate::handler::workspaces::get_workspaces,
+        crate::handler::workspaces::post_workspaces,
+        crate::handler::workspaces::get_workspace_by_id,
+        crate::handler::workspaces::get_workspace_members,
+        crate::handler::workspaces::post_workspace_members,
+        crate::handler::workspaces::delete_workspace_member,
     ),
     components(
         schemas(
             ServiceStatusResponse,
             ErrorResponse,
+            CredentialsRequest,
+            RefreshRequest,
+            UserResponse,
+            TokenResponse,
+            ApiKeyRequest,
+            KeyScope,
+            ApiKeyResponse,
+            NewApiKeyResponse,
+            ApiKeysResponse,
             NewTodoRequest,
             UpdateTodoRequest,
+            TodoPriority,
+            TagMatch,
+            TagRequest,
+            TagResponse,
+            TagsResponse,
+            ListRequest,
+            ListDeletePolicy,
+            ListResponse,
+            ListsResponse,
             TodoResponse,
+            SubtaskRollup,
+            TodoTreeResponse,
+            MoveTodoRequest,
+            OccurrencesResponse,
+            EventKind,
+            TodoEventResponse,
+            TodoHistoryResponse,
+            CommentRequest,
+            CommentResponse,
+            CommentsResponse,
+            AttachmentUpload,
+            AttachmentResponse,
+            AttachmentsResponse,
+            Role,
+            ShareRequest,
+            ShareResponse,
+            SharesResponse,
+            WorkspaceRequest,
+            WorkspaceResponse,
+            WorkspacesResponse,
+            MemberRequest,
+            MemberResponse,
+            MembersResponse,
             TodosResponse,
+            TodoSearchResultResponse,
+            TodoSearchResponse,
+            BatchMode,
+            BatchTodoOperation,
+            BatchTodosRequest,
+            BatchTodoResult,
+            BatchTodosResponse,
         )
     ),
+    modifiers(&BearerAuth),
+    security(("bearer" = []), ("api_key" = [])),
     tags(
         (name = "crate::handler::server", description = "server routers"),
+        (name = "crate::handler::auth", description = "auth routers"),
+        (name = "crate::handler::oidc", description = "single sign-on routers"),
+        (name = "crate::handler::api_keys", description = "api keys routers"),
         (name = "crate::handler::todos", description = "todos routers"),
+        (name = "crate::handler::trash", description = "trash routers"),
+        (name = "crate::handler::tags", description = "tags routers"),
+        (name = "crate::handler::lists", description = "lists routers"),
+        (name = "crate::handler::comments", description = "comments routers"),
+        (name = "crate::handler::attachments", description = "attachments routers"),
+        (name = "crate::handler::shares", description = "shares routers"),
+        (name = "crate::handler::workspaces", description = "workspaces routers"),
     ),
 )]
 pub struct ApiDoc;
+
+// Access tokens from `/api/v1/auth/login` or API keys from `/api/v1/api-keys`, one of which is
+// required by every route that does not opt out
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new("X-Api-Key"))),
+            );
+        }
+    }
+}
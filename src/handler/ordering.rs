@@ -0,0 +1,611 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use axum_extra::headers::HeaderMapExt;
+use chrono::Utc;
+use garde::Validate;
+use http::HeaderMap;
+
+use entity::sea_orm_active_enums::TodoEventKind;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use entity::todos::Model as TodosModel;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, Statement, Value,
+};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::handler::history::record_event;
+use crate::handler::todos::{check_if_match, find_live_todo, todo_response, update_todo};
+use crate::{
+    dto::{Audit, CustomMethodTarget, MoveTodoRequest, Role, TodoResponse},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+// Ranks are base 36 fractions without trailing zeros, so their byte order is their numeric order
+const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const BASE: usize = DIGITS.len();
+// Digits appended ranks count up in, enough for a billion appends behind evenly spaced ranks
+const APPEND_WIDTH: usize = 6;
+// Longest rank whose value fits in a u128
+const MAX_WIDTH: usize = 24;
+// Todos on each side of a crowded gap respaced at first, the window grows until they fit
+const REBALANCE_WINDOW: u64 = 16;
+
+// Where a todo goes, or the todo right before the gap that has no room left for it
+enum Placement {
+    Rank(String),
+    Crowded(Option<(String, i64)>),
+}
+
+#[utoipa::path(
+    post,
+    request_body = MoveTodoRequest,
+    path = "/api/v1/todos/{id}:move",
+    responses(
+        (status = 200, description = "move todo to a new position in the manual order", body = [TodoResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
+        (status = 422, description = "neighbours not found or not in order", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to move"),
+    )
+)]
+pub async fn move_todo_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(target), _): WithRejection<Path<CustomMethodTarget>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
+    WithRejection(Json(payload), _): WithRejection<Json<MoveTodoRequest>, ServiceError>,
+) -> AppResult<TodoResponse> {
+    payload.validate(&())?;
+
+    let id = target.id;
+    if payload.before == Some(id) || payload.after == Some(id) {
+        return Err(ServiceError::InvalidMoveError(format!(
+            "todo {id} cannot be moved next to itself"
+        )));
+    }
+
+    let txn = workspace.begin(&state).await?;
+
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    check_if_match(&state, headers.typed_get(), id, Some(&todo))?;
+
+    let before = todo_response(&txn, todo.clone()).await?;
+    let rank = match place(&state, &txn, workspace.id, user.id, id, &payload).await? {
+        Placement::Rank(rank) => rank,
+        Placement::Crowded(previous) => {
+            // the moved todo is left out, it gets a new rank anyway
+            rebalance_ranks(&state, &txn, workspace.id, previous, Some(id)).await?;
+            // evenly spaced ranks always leave room between two todos
+            match place(&state, &txn, workspace.id, user.id, id, &payload).await? {
+                Placement::Rank(rank) => rank,
+                Placement::Crowded(_) => {
+                    return Err(ServiceError::InvalidMoveError(format!(
+                        "cannot move todo {id}"
+                    )))
+                }
+            }
+        }
+    };
+
+    let res = update_todo(&txn, todo, |todo| {
+        todo.rank = ActiveValue::set(rank);
+        todo.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    })
+    .await?;
+    let res = todo_response(&txn, res).await?;
+    record_event(
+        &txn,
+        &audit,
+        TodoEventKind::Updated,
+        Some(&before),
+        Some(&res),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+// Rank between the requested neighbours, unless they are too close to fit one in
+//
+// Ranks are shared by the todos of all users of a workspace, a rank between two todos in that
+// order is also between them in the order of the todos one user can see.
+async fn place(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    user_id: i64,
+    id: i64,
+    payload: &MoveTodoRequest,
+) -> AppResult<Placement> {
+    let after = neighbour(db, workspace_id, user_id, payload.after).await?;
+    let before = neighbour(db, workspace_id, user_id, payload.before).await?;
+
+    let (previous, upper) = match (after, before) {
+        (Some(after), Some(before)) => {
+            if (&after.rank, after.id) >= (&before.rank, before.id) {
+                return Err(ServiceError::InvalidMoveError(format!(
+                    "todo {} does not come before todo {}",
+                    after.id, before.id
+                )));
+            }
+            let previous = (after.rank.clone(), after.id);
+            (Some(previous), Some(before.rank))
+        }
+        (Some(after), None) => {
+            let next = TodosEntity::find()
+                .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+                .filter(TodosColumn::Id.ne(id))
+                .filter(
+                    Expr::tuple([
+                        Expr::col(TodosColumn::Rank).into(),
+                        Expr::col(TodosColumn::Id).into(),
+                    ])
+                    .gt(Expr::tuple([
+                        Expr::val(after.rank.clone()).into(),
+                        Expr::val(after.id).into(),
+                    ])),
+                )
+                .order_by_asc(TodosColumn::Rank)
+                .order_by_asc(TodosColumn::Id)
+                .one(db)
+                .await?;
+            let previous = (after.rank.clone(), after.id);
+            (Some(previous), next.map(|todo| todo.rank))
+        }
+        (None, Some(before)) => {
+            let previous = TodosEntity::find()
+                .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+                .filter(TodosColumn::Id.ne(id))
+                .filter(
+                    Expr::tuple([
+                        Expr::col(TodosColumn::Rank).into(),
+                        Expr::col(TodosColumn::Id).into(),
+                    ])
+                    .lt(Expr::tuple([
+                        Expr::val(before.rank.clone()).into(),
+                        Expr::val(before.id).into(),
+                    ])),
+                )
+                .order_by_desc(TodosColumn::Rank)
+                .order_by_desc(TodosColumn::Id)
+                .one(db)
+                .await?;
+            (previous.map(|todo| (todo.rank, todo.id)), Some(before.rank))
+        }
+        // rejected by validation
+        (None, None) => {
+            return Err(ServiceError::InvalidMoveError(format!(
+                "cannot move todo {id}"
+            )))
+        }
+    };
+
+    let lower = previous.as_ref().map_or("", |(rank, _)| rank.as_str());
+    // todos sharing a rank, e.g. after concurrent creates, have nothing in between
+    if matches!(&upper, Some(upper) if lower >= upper.as_str()) {
+        return Ok(Placement::Crowded(previous));
+    }
+
+    let rank = rank_between(lower, upper.as_deref());
+    if rank.len() > state.config.ordering.rebalance_rank_length {
+        return Ok(Placement::Crowded(previous));
+    }
+    Ok(Placement::Rank(rank))
+}
+
+async fn neighbour(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    user_id: i64,
+    id: Option<i64>,
+) -> AppResult<Option<TodosModel>> {
+    match id {
+        Some(id) => match find_live_todo(db, workspace_id, user_id, id, Role::Viewer).await? {
+            Some(todo) => Ok(Some(todo)),
+            None => Err(ServiceError::MoveNeighbourNotFoundError(id)),
+        },
+        None => Ok(None),
+    }
+}
+
+// Rank behind every other todo of the workspace, used for new todos
+pub(crate) async fn last_rank(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+) -> AppResult<String> {
+    for _ in 0..2 {
+        let last = TodosEntity::find()
+            .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+            .order_by_desc(TodosColumn::Rank)
+            .order_by_desc(TodosColumn::Id)
+            .one(db)
+            .await?;
+
+        let last = match last {
+            Some(last) => (last.rank, last.id),
+            None => return Ok(rank_between("", None)),
+        };
+        let rank = rank_after(&last.0);
+        if rank.len() <= state.config.ordering.rebalance_rank_length {
+            return Ok(rank);
+        }
+
+        rebalance_ranks(state, db, workspace_id, Some(last), None).await?;
+    }
+
+    Err(ServiceError::InvalidMoveError(
+        "cannot find a rank behind the last todo".to_owned(),
+    ))
+}
+
+// Spreads the ranks of the todos around the gap behind `previous` evenly, keeping their order.
+// The window of todos grows until their ranks fit between the ones of the todos around it, so
+// that a crowded gap only respaces its neighbourhood. Todos whose rank changes get a new
+// version, as the order of the pages they are on changed.
+async fn rebalance_ranks(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    previous: Option<(String, i64)>,
+    exclude: Option<i64>,
+) -> AppResult<()> {
+    let max_length = state.config.ordering.rebalance_rank_length;
+    let mut size = REBALANCE_WINDOW;
+    loop {
+        let mut below = match &previous {
+            Some(previous) => walk(db, workspace_id, previous, exclude, size + 1, false).await?,
+            None => Vec::new(),
+        };
+        let mut above = match &previous {
+            Some(previous) => walk(db, workspace_id, previous, exclude, size + 1, true).await?,
+            None => first_todos(db, workspace_id, exclude, size + 1).await?,
+        };
+        // the todos just outside of the window bound it, the ends of the order are open
+        let lower = (below.len() as u64 > size).then(|| below.pop()).flatten();
+        let upper = (above.len() as u64 > size).then(|| above.pop()).flatten();
+
+        let ids: Vec<i64> = below
+            .iter()
+            .rev()
+            .chain(above.iter())
+            .map(|todo| todo.id)
+            .collect();
+        let lower_rank = lower.as_ref().map_or("", |todo| todo.rank.as_str());
+        let upper_rank = upper.as_ref().map(|todo| todo.rank.as_str());
+        if let Some(ranks) = spread_ranks(lower_rank, upper_rank, ids.len(), max_length) {
+            return update_ranks(db, ids.into_iter().zip(ranks).collect()).await;
+        }
+        if lower.is_none() && upper.is_none() {
+            return Err(ServiceError::InvalidMoveError(
+                "cannot respace the ranks of the workspace".to_owned(),
+            ));
+        }
+        size *= 4;
+    }
+}
+
+// Todos behind `from` in the order of the workspace, or those up to and including it backwards
+async fn walk(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    (rank, id): &(String, i64),
+    exclude: Option<i64>,
+    limit: u64,
+    forward: bool,
+) -> AppResult<Vec<TodosModel>> {
+    let key = Expr::tuple([
+        Expr::col(TodosColumn::Rank).into(),
+        Expr::col(TodosColumn::Id).into(),
+    ]);
+    let from = Expr::tuple([Expr::val(rank.clone()).into(), Expr::val(*id).into()]);
+    let mut select = TodosEntity::find()
+        .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+        .apply_if(exclude, |select, id| select.filter(TodosColumn::Id.ne(id)));
+    select = if forward {
+        select
+            .filter(key.gt(from))
+            .order_by_asc(TodosColumn::Rank)
+            .order_by_asc(TodosColumn::Id)
+    } else {
+        select
+            .filter(key.lte(from))
+            .order_by_desc(TodosColumn::Rank)
+            .order_by_desc(TodosColumn::Id)
+    };
+
+    Ok(select.limit(limit).all(db).await?)
+}
+
+async fn first_todos(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    exclude: Option<i64>,
+    limit: u64,
+) -> AppResult<Vec<TodosModel>> {
+    Ok(TodosEntity::find()
+        .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+        .apply_if(exclude, |select, id| select.filter(TodosColumn::Id.ne(id)))
+        .order_by_asc(TodosColumn::Rank)
+        .order_by_asc(TodosColumn::Id)
+        .limit(limit)
+        .all(db)
+        .await?)
+}
+
+async fn update_ranks(db: &impl ConnectionTrait, rows: Vec<(i64, String)>) -> AppResult<()> {
+    let now = Utc::now().naive_utc();
+    for chunk in rows.chunks(1000) {
+        let mut values: Vec<Value> = Vec::with_capacity(chunk.len() * 2 + 1);
+        values.push(now.into());
+        let mut placeholders = Vec::with_capacity(chunk.len());
+        for (id, rank) in chunk {
+            placeholders.push(format!(
+                "(${}::bigint, ${})",
+                values.len() + 1,
+                values.len() + 2
+            ));
+            values.push((*id).into());
+            values.push(rank.clone().into());
+        }
+
+        let sql = format!(
+            "UPDATE todos SET rank = ranked.rank, version = todos.version + 1, updated_at = $1 \
+             FROM (VALUES {}) AS ranked(id, rank) \
+             WHERE todos.id = ranked.id AND todos.rank <> ranked.rank",
+            placeholders.join(", ")
+        );
+        db.execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            sql,
+            values,
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+fn digit(d: u8) -> usize {
+    DIGITS.iter().position(|&x| x == d).unwrap_or(0)
+}
+
+// Shortest rank strictly between `lower` and `upper`, an absent upper bound is open.
+// The empty rank is the lowest one, `lower` must come before `upper`
+fn rank_between(lower: &str, upper: Option<&str>) -> String {
+    let mut rank = Vec::new();
+    midpoint(lower.as_bytes(), upper.map(str::as_bytes), &mut rank);
+    String::from_utf8(rank).unwrap_or_default()
+}
+
+fn midpoint(lower: &[u8], upper: Option<&[u8]>, rank: &mut Vec<u8>) {
+    if let Some(upper) = upper {
+        // keep the common prefix, missing digits of `lower` are zeros
+        let common = upper
+            .iter()
+            .enumerate()
+            .take_while(|&(i, &d)| lower.get(i).copied().unwrap_or(DIGITS[0]) == d)
+            .count();
+        if common > 0 {
+            rank.extend_from_slice(&upper[..common]);
+            let lower = lower.get(common..).unwrap_or_default();
+            return midpoint(lower, Some(&upper[common..]), rank);
+        }
+    }
+
+    let low = lower.first().map_or(0, |&d| digit(d));
+    let high = upper
+        .and_then(|upper| upper.first())
+        .map_or(BASE, |&d| digit(d));
+    if high - low > 1 {
+        rank.push(DIGITS[(low + high) / 2]);
+    } else if let Some(upper) = upper.filter(|upper| upper.len() > 1) {
+        // the first digit of `upper` alone already comes before it
+        rank.push(upper[0]);
+    } else {
+        rank.push(DIGITS[low]);
+        midpoint(lower.get(1..).unwrap_or_default(), None, rank);
+    }
+}
+
+// Rank behind `rank`, counts up in its last digit at a fixed width so that appending many todos
+// in a row does not make their ranks any longer
+fn rank_after(rank: &str) -> String {
+    let mut next = rank.as_bytes().to_vec();
+    next.resize(next.len().max(APPEND_WIDTH), DIGITS[0]);
+    for d in next.iter_mut().rev() {
+        if digit(*d) < BASE - 1 {
+            *d = DIGITS[digit(*d) + 1];
+            while next.last() == Some(&DIGITS[0]) {
+                next.pop();
+            }
+            return String::from_utf8(next).unwrap_or_default();
+        }
+        *d = DIGITS[0];
+    }
+
+    // nothing left at this width
+    rank_between(rank, None)
+}
+
+// `count` evenly spaced ranks strictly between `lower` and `upper`, each with room for a digit
+// more in between, none if that takes more than `max_length` digits. An open upper bound only
+// uses the lower half of what is left, so that later appends have room behind them.
+fn spread_ranks(
+    lower: &str,
+    upper: Option<&str>,
+    count: usize,
+    max_length: usize,
+) -> Option<Vec<String>> {
+    let slots = count as u128 + 1;
+    let shortest = lower.len().max(upper.map_or(0, str::len)).max(1);
+    for width in shortest..=max_length.min(MAX_WIDTH) {
+        let scale = (BASE as u128).pow(width as u32);
+        let low = decode_rank(lower, width);
+        let high = match upper {
+            Some(upper) => decode_rank(upper, width),
+            None => low + (scale - low) / 2,
+        };
+        if high <= low {
+            return None;
+        }
+
+        let step = (high - low) / slots;
+        if step >= BASE as u128 {
+            return Some(
+                (1..slots)
+                    .map(|i| encode_rank(low + i * step, width))
+                    .collect(),
+            );
+        }
+    }
+
+    None
+}
+
+// Value of a rank of at most `width` digits as a multiple of the smallest step at that width
+fn decode_rank(rank: &str, width: usize) -> u128 {
+    let mut bytes = rank.as_bytes().to_vec();
+    bytes.resize(width, DIGITS[0]);
+    bytes
+        .iter()
+        .fold(0, |value, &d| value * BASE as u128 + digit(d) as u128)
+}
+
+fn encode_rank(mut value: u128, width: usize) -> String {
+    let mut rank = vec![DIGITS[0]; width];
+    for d in rank.iter_mut().rev() {
+        *d = DIGITS[(value % BASE as u128) as usize];
+        value /= BASE as u128;
+    }
+    while rank.last() == Some(&DIGITS[0]) {
+        rank.pop();
+    }
+    String::from_utf8(rank).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_between(lower: &str, rank: &str, upper: Option<&str>) {
+        assert!(lower < rank, "{lower:?} < {rank:?}");
+        if let Some(upper) = upper {
+            assert!(rank < upper, "{rank:?} < {upper:?}");
+        }
+        assert!(!rank.ends_with('0'), "{rank:?} has a trailing zero");
+    }
+
+    #[test]
+    fn rank_between_is_strictly_between() {
+        let bounds = [
+            ("", None),
+            ("", Some("1")),
+            ("", Some("01")),
+            ("", Some("001")),
+            ("a", None),
+            ("z", None),
+            ("zz", None),
+            ("a", Some("c")),
+            ("a", Some("b")),
+            ("a", Some("a1")),
+            ("a", Some("a01")),
+            ("az", Some("b")),
+            ("azz", Some("b1")),
+            ("h5", Some("h6")),
+            ("0x", Some("1")),
+        ];
+        for (lower, upper) in bounds {
+            assert_between(lower, &rank_between(lower, upper), upper);
+        }
+    }
+
+    #[test]
+    fn rank_between_adjacent_digits_adds_one() {
+        assert_eq!(rank_between("a", Some("b")), "ai");
+        assert_eq!(rank_between("", None), "i");
+    }
+
+    #[test]
+    fn repeated_inserts_keep_their_order() {
+        let (lower, mut upper) = ("a".to_owned(), "b".to_owned());
+        for _ in 0..100 {
+            let rank = rank_between(&lower, Some(&upper));
+            assert_between(&lower, &rank, Some(&upper));
+            upper = rank;
+        }
+    }
+
+    #[test]
+    fn rank_after_comes_after() {
+        for rank in ["0001", "h", "hzzzzz", "z", "zzzzzy", "zzzzzz", "a1b2c3d4"] {
+            let next = rank_after(rank);
+            assert_between(rank, &next, None);
+        }
+    }
+
+    #[test]
+    fn appends_do_not_grow_ranks() {
+        let mut rank = "h".to_owned();
+        for _ in 0..100_000 {
+            let next = rank_after(&rank);
+            assert!(next > rank);
+            rank = next;
+        }
+        assert!(rank.len() <= APPEND_WIDTH);
+    }
+
+    #[test]
+    fn spread_ranks_keep_their_order_between_the_bounds() {
+        let bounds = [("", None), ("a", Some("b")), ("a", Some("a1")), ("h", None)];
+        for (lower, upper) in bounds {
+            for count in [1, 2, 35, 36, 1000] {
+                let ranks = spread_ranks(lower, upper, count, MAX_WIDTH).unwrap();
+                assert_eq!(ranks.len(), count);
+                assert_between(lower, &ranks[0], upper);
+                for pair in ranks.windows(2) {
+                    assert_between(&pair[0], &pair[1], upper);
+                    // room for another one in between
+                    assert_between(
+                        &pair[0],
+                        &rank_between(&pair[0], Some(&pair[1])),
+                        Some(&pair[1]),
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn spread_ranks_leave_room_for_appends() {
+        let ranks = spread_ranks("", None, 10_000, MAX_WIDTH).unwrap();
+        assert!(ranks.last().unwrap().as_str() < "i");
+
+        let ranks = spread_ranks("x", None, 100, MAX_WIDTH).unwrap();
+        assert!(ranks.last().unwrap().as_str() < "yi");
+    }
+
+    #[test]
+    fn spread_ranks_fail_without_room() {
+        assert!(spread_ranks("b", Some("a"), 1, MAX_WIDTH).is_none());
+        assert!(spread_ranks("a", Some("a"), 1, MAX_WIDTH).is_none());
+        assert!(spread_ranks("a", Some("a0001"), 100, 6).is_none());
+        assert_eq!(spread_ranks("a", Some("b"), 0, MAX_WIDTH), Some(Vec::new()));
+    }
+}
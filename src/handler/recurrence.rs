@@ -0,0 +1,163 @@
+use axum::extract::{Path, Query, State};
+use axum_extra::extract::WithRejection;
+use chrono::{Duration, NaiveDateTime as DateTime, TimeZone, Utc};
+use rrule::Tz;
+
+use entity::todos::ActiveModel as TodosActiveModel;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Model as TodosModel;
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::handler::{
+    ordering::last_rank,
+    tags::set_todo_tags,
+    todos::{find_live_todo, touch_todos},
+};
+use crate::{
+    dto::{recurrence_set, OccurrencesQuery, OccurrencesResponse, Role, Validated},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/occurrences",
+    responses(
+        (status = 200, description = "preview upcoming due dates of a recurring todo", body = [OccurrencesResponse]),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 422, description = "todo does not recur", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to preview occurrences for"),
+        OccurrencesQuery,
+    )
+)]
+pub async fn get_todo_occurrences(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<OccurrencesQuery>>,
+        ServiceError,
+    >,
+) -> AppResult<OccurrencesResponse> {
+    let txn = workspace.begin(&state).await?;
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    txn.commit().await?;
+    let (rule, start) = match (&todo.recurrence, series_start(&todo)) {
+        (Some(rule), Some(start)) => (rule, start),
+        _ => return Err(ServiceError::TodoNotRecurringError(id)),
+    };
+
+    let pagination = &state.config.pagination;
+    let limit = query
+        .limit
+        .unwrap_or(pagination.default_page_size)
+        .min(pagination.max_page_size)
+        .min(u16::MAX as u64 - 1) as usize;
+
+    let mut occurrences = recurrence_set(rule, start)?
+        .after(utc(query.from.unwrap_or_else(|| Utc::now().naive_utc())));
+    if let Some(to) = query.to {
+        occurrences = occurrences.before(utc(to));
+    }
+    // one more than asked for tells whether there are more
+    let mut occurrences: Vec<DateTime> = occurrences
+        .all(limit as u16 + 1)
+        .dates
+        .into_iter()
+        .map(|occurrence| occurrence.naive_utc())
+        .collect();
+    let has_more = occurrences.len() > limit;
+    occurrences.truncate(limit);
+
+    Ok(OccurrencesResponse {
+        occurrences,
+        has_more,
+    })
+}
+
+// The series keeps its start while the rule is unchanged, a new rule starts at the due date
+pub(crate) fn recurrence_start(
+    recurrence: &Option<String>,
+    due_at: Option<DateTime>,
+    todo: Option<&TodosModel>,
+) -> Option<DateTime> {
+    match todo {
+        _ if recurrence.is_none() => None,
+        Some(todo) if todo.recurrence == *recurrence && todo.recurrence_start.is_some() => {
+            todo.recurrence_start
+        }
+        _ => due_at,
+    }
+}
+
+fn series_start(todo: &TodosModel) -> Option<DateTime> {
+    todo.recurrence_start.or(todo.due_at)
+}
+
+// Due date of the occurrence following the one due at `due_at`, none once the series ends
+pub(crate) fn next_occurrence(
+    recurrence: &Option<String>,
+    start: Option<DateTime>,
+    due_at: Option<DateTime>,
+) -> AppResult<Option<DateTime>> {
+    let (rule, start, due_at) = match (recurrence, start, due_at) {
+        (Some(rule), Some(start), Some(due_at)) => (rule, start, due_at),
+        _ => return Ok(None),
+    };
+
+    // `after` is inclusive, an occurrence at the current due date is the current one
+    let next = recurrence_set(rule, start)?
+        .after(utc(due_at + Duration::microseconds(1)))
+        .all(1)
+        .dates
+        .first()
+        .map(|next| next.naive_utc());
+
+    Ok(next)
+}
+
+// Creates the next occurrence of the complated `todo`, which hands its rule over to it
+pub(crate) async fn spawn_occurrence(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    todo: &TodosModel,
+    tags: &[String],
+    recurrence: (String, DateTime),
+    due_at: DateTime,
+) -> AppResult<TodosModel> {
+    let (rule, start) = recurrence;
+    let occurrence = TodosActiveModel {
+        body: ActiveValue::set(todo.body.clone()),
+        complated: ActiveValue::set(false),
+        due_at: ActiveValue::set(Some(due_at)),
+        priority: ActiveValue::set(todo.priority),
+        list_id: ActiveValue::set(todo.list_id),
+        parent_id: ActiveValue::set(todo.parent_id),
+        rank: ActiveValue::set(last_rank(state, db, todo.workspace_id).await?),
+        recurrence: ActiveValue::set(Some(rule)),
+        recurrence_start: ActiveValue::set(Some(start)),
+        owner_id: ActiveValue::set(todo.owner_id),
+        workspace_id: ActiveValue::set(todo.workspace_id),
+        ..Default::default()
+    };
+
+    let res = occurrence.insert(db).await?;
+    set_todo_tags(db, res.workspace_id, res.id, tags).await?;
+    if let Some(parent_id) = res.parent_id {
+        touch_todos(db, TodosColumn::Id.eq(parent_id)).await?;
+    }
+
+    Ok(res)
+}
+
+fn utc(value: DateTime) -> chrono::DateTime<Tz> {
+    Tz::UTC.from_utc_datetime(&value)
+}
@@ -8,7 +8,8 @@ use crate::{dto::ServiceStatusResponse, error::AppResult, server::AppState};
     path = "/health",
     responses(
         (status = 204, description = "check service is up")
-    )
+    ),
+    security(())
 )]
 pub async fn health() -> AppResult<StatusCode> {
     Ok(StatusCode::NO_CONTENT)
@@ -19,7 +20,8 @@ pub async fn health() -> AppResult<StatusCode> {
     path = "/state",
     responses(
         (status = 200, description = "state of inner connection services", body = [ServiceStatusResponse])
-    )
+    ),
+    security(())
 )]
 pub async fn state(State(state): State<AppState>) -> AppResult<ServiceStatusResponse> {
     let database_status = state.database.ping().await;
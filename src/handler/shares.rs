@@ -0,0 +1,207 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use garde::Validate;
+use http::StatusCode;
+
+use entity::shares::ActiveModel as SharesActiveModel;
+use entity::shares::Column as SharesColumn;
+use entity::shares::Entity as SharesEntity;
+use entity::users::Column as UsersColumn;
+use entity::users::Entity as UsersEntity;
+use entity::workspaces::Column as WorkspacesColumn;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder,
+};
+
+use crate::auth::{find_workspace, CurrentUser, CurrentWorkspace};
+use crate::handler::{lists::check_list_exists, todos::find_live_todo};
+use crate::{
+    dto::{Role, ShareRequest, ShareResponse, SharesResponse},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/shares",
+    responses(
+        (status = 200, description = "get the shares made by and with the user, newest first", body = [SharesResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn get_shares(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+) -> AppResult<SharesResponse> {
+    let txn = workspace.begin(&state).await?;
+    let shares = SharesEntity::find()
+        .filter(involving(workspace.id, user.id))
+        .order_by_desc(SharesColumn::Id)
+        .all(&txn)
+        .await?;
+    txn.commit().await?;
+
+    Ok(SharesResponse {
+        shares: shares.into_iter().map(Into::into).collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = ShareRequest,
+    path = "/api/v1/shares",
+    responses(
+        (status = 200, description = "change the role of an existing share", body = [ShareResponse]),
+        (status = 201, description = "share a todo, or every todo of the user in a list", body = [ShareResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 403, description = "only the owner can share a todo", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 422, description = "unknown user or list, user outside of the workspace, or sharing with oneself", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn post_shares(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Json(payload), _): WithRejection<Json<ShareRequest>, ServiceError>,
+) -> AppResult<(StatusCode, ShareResponse)> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    let email = payload.email.to_lowercase();
+    let grantee = UsersEntity::find()
+        .filter(UsersColumn::Email.eq(&email))
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::UnknownShareUserError(email.clone()))?;
+    // todos cannot be shared past the members of their workspace
+    find_workspace(&txn, grantee.id, WorkspacesColumn::Id.eq(workspace.id))
+        .await?
+        .ok_or(ServiceError::UnknownShareUserError(email))?;
+    if grantee.id == user.id {
+        return Err(ServiceError::InvalidShareError(
+            "todos cannot be shared with their owner".to_owned(),
+        ));
+    }
+
+    // a list share covers the todos of the user in it, whoever else has todos there
+    let target = match (payload.todo_id, payload.list_id) {
+        (Some(todo_id), _) => {
+            find_live_todo(&txn, workspace.id, user.id, todo_id, Role::Owner)
+                .await?
+                .ok_or(ServiceError::TodoNotFoundError(todo_id))?;
+            SharesColumn::TodoId.eq(todo_id)
+        }
+        (None, list_id) => {
+            check_list_exists(&txn, workspace.id, list_id).await?;
+            SharesColumn::ListId.eq(list_id)
+        }
+    };
+
+    let existing = SharesEntity::find()
+        .filter(SharesColumn::WorkspaceId.eq(workspace.id))
+        .filter(SharesColumn::OwnerId.eq(user.id))
+        .filter(SharesColumn::UserId.eq(grantee.id))
+        .filter(target)
+        .one(&txn)
+        .await?;
+    let (status, res) = match existing {
+        Some(share) => {
+            let mut share: SharesActiveModel = share.into();
+            share.role = ActiveValue::set(payload.share_role());
+            (StatusCode::OK, share.update(&txn).await?)
+        }
+        None => {
+            let share = SharesActiveModel {
+                workspace_id: ActiveValue::set(workspace.id),
+                owner_id: ActiveValue::set(user.id),
+                user_id: ActiveValue::set(grantee.id),
+                todo_id: ActiveValue::set(payload.todo_id),
+                list_id: ActiveValue::set(payload.list_id),
+                role: ActiveValue::set(payload.share_role()),
+                ..Default::default()
+            };
+            (StatusCode::CREATED, share.insert(&txn).await?)
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok((status, res.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/shares/{id}",
+    responses(
+        (status = 200, description = "get share by id", body = [ShareResponse]),
+        (status = 404, description = "share not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "share database id"),
+    )
+)]
+pub async fn get_share_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<ShareResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = SharesEntity::find_by_id(id)
+        .filter(involving(workspace.id, user.id))
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::ShareNotFoundError(id))?;
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/shares/{id}",
+    responses(
+        (status = 200, description = "stop sharing, either as the owner or as the user shared with"),
+        (status = 204, description = "share not found"),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "share database id to delete"),
+    )
+)]
+pub async fn delete_share_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+    let res = SharesEntity::delete_many()
+        .filter(SharesColumn::Id.eq(id))
+        .filter(involving(workspace.id, user.id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    match res.rows_affected {
+        0 => Ok(StatusCode::NO_CONTENT),
+        _ => Ok(StatusCode::OK),
+    }
+}
+
+// Shares are visible to both sides of them, in the workspace they were made in
+fn involving(workspace_id: i64, user_id: i64) -> Condition {
+    Condition::all()
+        .add(SharesColumn::WorkspaceId.eq(workspace_id))
+        .add(
+            Condition::any()
+                .add(SharesColumn::OwnerId.eq(user_id))
+                .add(SharesColumn::UserId.eq(user_id)),
+        )
+}
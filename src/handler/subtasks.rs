@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, Query, State};
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use http::HeaderMap;
+
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use sea_orm::sea_query::{Expr, IntoCondition};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
+    QuerySelect, Statement,
+};
+
+use crate::auth::{visible_todos, CurrentUser, CurrentWorkspace};
+use crate::handler::todos::{find_live_todo, list_todos, live_todos, todo_responses};
+use crate::{
+    dto::{
+        Conditional, GetTodosQuery, Role, SubtaskRollup, TodoResponse, TodoTreeResponse,
+        TodosResponse, Validated,
+    },
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+// The todo itself and every subtask below it that is not in the trash, UNION stops at cycles
+const IN_SUBTREE_SQL: &str = r#"
+id IN (
+    WITH RECURSIVE subtree(id) AS (
+        SELECT id FROM todos WHERE id = $1 AND deleted_at IS NULL
+        UNION
+        SELECT todos.id FROM todos JOIN subtree ON todos.parent_id = subtree.id
+        WHERE todos.deleted_at IS NULL
+    )
+    SELECT id FROM subtree
+)
+"#;
+
+// Whether $2 is $1 or one of its ancestors, UNION stops at cycles already in the table
+const IS_ANCESTOR_SQL: &str = r#"
+WITH RECURSIVE ancestors(id, parent_id) AS (
+    SELECT id, parent_id FROM todos WHERE id = $1
+    UNION
+    SELECT todos.id, todos.parent_id FROM todos JOIN ancestors ON todos.id = ancestors.parent_id
+)
+SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = $2) AS is_ancestor
+"#;
+
+// Re-parenting todos of workspace $1 one transaction at a time, two concurrent moves could
+// otherwise each pass the check for a cycle they only form together
+const LOCK_PARENTS_SQL: &str = r#"
+SELECT pg_advisory_xact_lock(hashtextextended('olivier.todo_parents', $1))
+"#;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/children",
+    responses(
+        (status = 200, description = "get a page of filtered and sorted direct subtasks", body = [TodosResponse],
+            headers(("etag" = String, description = "hash of the page"), ("last-modified" = String))),
+        (status = 304, description = "page matches If-None-Match or is unchanged since If-Modified-Since"),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to get subtasks for"),
+        GetTodosQuery,
+    )
+)]
+pub async fn get_todo_children(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<GetTodosQuery>>,
+        ServiceError,
+    >,
+    headers: HeaderMap,
+) -> AppResult<Conditional<TodosResponse>> {
+    let txn = workspace.begin(&state).await?;
+
+    find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    let select = live_todos(workspace.id, user.id).filter(TodosColumn::ParentId.eq(id));
+    let todos = list_todos(&state, &txn, select, query).await?;
+
+    txn.commit().await?;
+
+    Ok(Conditional::new(&headers, todos))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/{id}/subtree",
+    responses(
+        (status = 200, description = "get todo with all of its subtasks nested below it", body = [TodoTreeResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id of the subtree root"),
+    )
+)]
+pub async fn get_todo_subtree(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<TodoTreeResponse> {
+    let txn = workspace.begin(&state).await?;
+
+    // loaded through the entity so that enum columns are decoded like everywhere else,
+    // subtasks shared on their own show up without the rest of the tree
+    let res = TodosEntity::find()
+        .filter(visible_todos(workspace.id, user.id))
+        .filter(Expr::cust_with_values(IN_SUBTREE_SQL, [id]))
+        .order_by_asc(TodosColumn::CreatedAt)
+        .order_by_asc(TodosColumn::Id)
+        .all(&txn)
+        .await?;
+
+    let mut root = None;
+    let mut children: HashMap<i64, Vec<TodoResponse>> = HashMap::new();
+    for todo in todo_responses(&txn, res).await? {
+        match todo.parent_id {
+            Some(parent_id) if todo.id != id => children.entry(parent_id).or_default().push(todo),
+            _ => root = Some(todo),
+        }
+    }
+
+    let root = root.ok_or(ServiceError::TodoNotFoundError(id))?;
+
+    txn.commit().await?;
+
+    Ok(build_tree(root, &mut children))
+}
+
+fn build_tree(
+    todo: TodoResponse,
+    children: &mut HashMap<i64, Vec<TodoResponse>>,
+) -> TodoTreeResponse {
+    let subtasks = children.remove(&todo.id).unwrap_or_default();
+
+    TodoTreeResponse {
+        children: subtasks
+            .into_iter()
+            .map(|subtask| build_tree(subtask, children))
+            .collect(),
+        todo,
+    }
+}
+
+// The parent must be a todo of the same owner and workspace outside of the trash and,
+// when re-parenting todo `id`, not below it. Must run in the transaction that re-parents.
+pub(crate) async fn check_parent(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    owner_id: Option<i64>,
+    id: Option<i64>,
+    parent_id: Option<i64>,
+) -> AppResult<()> {
+    let parent_id = match parent_id {
+        Some(parent_id) => parent_id,
+        None => return Ok(()),
+    };
+
+    let parent = TodosEntity::find_by_id(parent_id)
+        .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+        .filter(TodosColumn::DeletedAt.is_null())
+        .one(db)
+        .await?;
+    if !matches!(parent, Some(parent) if parent.owner_id == owner_id) {
+        return Err(ServiceError::UnknownParentError(parent_id));
+    }
+
+    if let Some(id) = id {
+        db.execute(Statement::from_sql_and_values(
+            db.get_database_backend(),
+            LOCK_PARENTS_SQL,
+            [workspace_id.into()],
+        ))
+        .await?;
+        let res = db
+            .query_one(Statement::from_sql_and_values(
+                db.get_database_backend(),
+                IS_ANCESTOR_SQL,
+                [parent_id.into(), id.into()],
+            ))
+            .await?;
+        let is_ancestor = match res {
+            Some(row) => row.try_get::<bool>("", "is_ancestor")?,
+            None => false,
+        };
+        if is_ancestor {
+            return Err(ServiceError::ParentCycleError(id, parent_id));
+        }
+    }
+
+    Ok(())
+}
+
+// Promotes the subtasks matching `condition` before their parent is purged
+pub(crate) async fn detach_subtasks(
+    db: &impl ConnectionTrait,
+    condition: impl IntoCondition,
+) -> AppResult<()> {
+    TodosEntity::update_many()
+        .col_expr(TodosColumn::ParentId, Expr::value(Option::<i64>::None))
+        .col_expr(TodosColumn::UpdatedAt, Expr::value(Utc::now().naive_utc()))
+        .col_expr(TodosColumn::Version, Expr::col(TodosColumn::Version).add(1))
+        .filter(condition)
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SubtaskCountRow {
+    parent_id: i64,
+    complated: bool,
+    count: i64,
+}
+
+pub(crate) async fn subtask_rollups(
+    db: &impl ConnectionTrait,
+    ids: &[i64],
+) -> AppResult<HashMap<i64, SubtaskRollup>> {
+    // subtasks belong to the owner of their parent, so the ids alone scope the counts
+    let counts = TodosEntity::find()
+        .filter(TodosColumn::DeletedAt.is_null())
+        .select_only()
+        .column(TodosColumn::ParentId)
+        .column(TodosColumn::Complated)
+        .column_as(Expr::col(TodosColumn::Id).count(), "count")
+        .filter(TodosColumn::ParentId.is_in(ids.iter().copied()))
+        .group_by(TodosColumn::ParentId)
+        .group_by(TodosColumn::Complated)
+        .into_model::<SubtaskCountRow>()
+        .all(db)
+        .await?;
+
+    let mut rollups: HashMap<i64, SubtaskRollup> = HashMap::new();
+    for row in counts {
+        let rollup = rollups.entry(row.parent_id).or_default();
+        rollup.total += row.count;
+        if row.complated {
+            rollup.completed += row.count;
+        }
+    }
+
+    Ok(rollups)
+}
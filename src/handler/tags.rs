@@ -0,0 +1,273 @@
+use std::collections::BTreeSet;
+
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use chrono::Utc;
+use garde::Validate;
+use http::StatusCode;
+
+use entity::tags::ActiveModel as TagsActiveModel;
+use entity::tags::Column as TagsColumn;
+use entity::tags::Entity as TagsEntity;
+use entity::tags::Model as TagsModel;
+use entity::todo_tags::ActiveModel as TodoTagsActiveModel;
+use entity::todo_tags::Column as TodoTagsColumn;
+use entity::todo_tags::Entity as TodoTagsEntity;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, SqlErr,
+};
+
+use crate::auth::CurrentWorkspace;
+use crate::{
+    dto::{TagRequest, TagResponse, TagsResponse},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags",
+    responses(
+        (status = 200, description = "get all tags sorted by name", body = [TagsResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn get_tags(
+    State(state): State<AppState>,
+    workspace: CurrentWorkspace,
+) -> AppResult<TagsResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = TagsEntity::find()
+        .filter(TagsColumn::WorkspaceId.eq(workspace.id))
+        .order_by_asc(TagsColumn::Name)
+        .all(&txn)
+        .await?;
+    txn.commit().await?;
+
+    Ok(TagsResponse {
+        tags: res.into_iter().map(|x| x.into()).collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = TagRequest,
+    path = "/api/v1/tags",
+    responses(
+        (status = 201, description = "create tag", body = [TagResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 409, description = "tag name is taken", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn post_tags(
+    State(state): State<AppState>,
+    workspace: CurrentWorkspace,
+    WithRejection(Json(payload), _): WithRejection<Json<TagRequest>, ServiceError>,
+) -> AppResult<(StatusCode, TagResponse)> {
+    payload.validate(&())?;
+
+    let tag = TagsActiveModel {
+        workspace_id: ActiveValue::set(workspace.id),
+        name: ActiveValue::set(payload.name.clone()),
+        ..Default::default()
+    };
+
+    let txn = workspace.begin(&state).await?;
+    let res = tag
+        .insert(&txn)
+        .await
+        .map_err(|err| name_conflict(err, payload.name))?;
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags/{id}",
+    responses(
+        (status = 200, description = "get tag", body = [TagResponse]),
+        (status = 404, description = "tag not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "tag database id to get tag for"),
+    )
+)]
+pub async fn get_tag_by_id(
+    State(state): State<AppState>,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<TagResponse> {
+    let txn = workspace.begin(&state).await?;
+    let res = find_tag(&txn, workspace.id, id)
+        .await?
+        .ok_or(ServiceError::TagNotFoundError(id))?;
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    put,
+    request_body = TagRequest,
+    path = "/api/v1/tags/{id}",
+    responses(
+        (status = 200, description = "rename tag", body = [TagResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 404, description = "tag not found", body = [ErrorResponse]),
+        (status = 409, description = "tag name is taken", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "tag database id to rename"),
+    )
+)]
+pub async fn put_tag_by_id(
+    State(state): State<AppState>,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Json(payload), _): WithRejection<Json<TagRequest>, ServiceError>,
+) -> AppResult<TagResponse> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    let tag = find_tag(&txn, workspace.id, id)
+        .await?
+        .ok_or(ServiceError::TagNotFoundError(id))?;
+
+    let mut tag: TagsActiveModel = tag.into();
+    tag.name = ActiveValue::set(payload.name.clone());
+    tag.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    let res = tag
+        .update(&txn)
+        .await
+        .map_err(|err| name_conflict(err, payload.name))?;
+    touch_tagged_todos(&txn, id).await?;
+
+    txn.commit().await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tags/{id}",
+    responses(
+        (status = 200, description = "delete tag and remove it from all todos"),
+        (status = 204, description = "tag not found"),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "tag database id to delete"),
+    )
+)]
+pub async fn delete_tag_by_id(
+    State(state): State<AppState>,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    if find_tag(&txn, workspace.id, id).await?.is_none() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    // touched before the delete cascades to `todo_tags`
+    touch_tagged_todos(&txn, id).await?;
+    TagsEntity::delete_by_id(id).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(StatusCode::OK)
+}
+
+async fn find_tag(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    id: i64,
+) -> AppResult<Option<TagsModel>> {
+    let res = TagsEntity::find_by_id(id)
+        .filter(TagsColumn::WorkspaceId.eq(workspace_id))
+        .one(db)
+        .await?;
+
+    Ok(res)
+}
+
+fn name_conflict(err: DbErr, name: String) -> ServiceError {
+    match err.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => ServiceError::TagNameConflictError(name),
+        _ => err.into(),
+    }
+}
+
+// Tag names are part of the todo representation, so renaming one changes every todo carrying it
+async fn touch_tagged_todos(db: &impl ConnectionTrait, tag_id: i64) -> AppResult<()> {
+    TodosEntity::update_many()
+        .col_expr(TodosColumn::Version, Expr::col(TodosColumn::Version).add(1))
+        .col_expr(TodosColumn::UpdatedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(
+            TodosColumn::Id.in_subquery(
+                Query::select()
+                    .column(TodoTagsColumn::TodoId)
+                    .from(TodoTagsEntity)
+                    .and_where(TodoTagsColumn::TagId.eq(tag_id))
+                    .to_owned(),
+            ),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+// Replaces the tags of a todo, every name must belong to an existing tag of its workspace
+pub(crate) async fn set_todo_tags(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    todo_id: i64,
+    names: &[String],
+) -> AppResult<()> {
+    let names: BTreeSet<&str> = names.iter().map(String::as_str).collect();
+    let tags: Vec<TagsModel> = match names.is_empty() {
+        true => Vec::new(),
+        false => {
+            TagsEntity::find()
+                .filter(TagsColumn::WorkspaceId.eq(workspace_id))
+                .filter(TagsColumn::Name.is_in(names.iter().copied()))
+                .all(db)
+                .await?
+        }
+    };
+    if tags.len() < names.len() {
+        let unknown: Vec<&str> = names
+            .into_iter()
+            .filter(|name| !tags.iter().any(|tag| tag.name == *name))
+            .collect();
+        return Err(ServiceError::UnknownTagError(unknown.join(", ")));
+    }
+
+    TodoTagsEntity::delete_many()
+        .filter(TodoTagsColumn::TodoId.eq(todo_id))
+        .exec(db)
+        .await?;
+    if !tags.is_empty() {
+        TodoTagsEntity::insert_many(tags.into_iter().map(|tag| TodoTagsActiveModel {
+            todo_id: ActiveValue::set(todo_id),
+            tag_id: ActiveValue::set(tag.id),
+        }))
+        .exec_without_returning(db)
+        .await?;
+    }
+
+    Ok(())
+}
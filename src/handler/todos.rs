@@ -1,36 +1,292 @@
-use axum::extract::Path;
+use std::collections::BTreeSet;
+
+use axum::body::Bytes;
+use axum::extract::{Path, Query, Request};
+use axum::handler::Handler;
+use axum::response::{IntoResponse, Response};
 use axum::{extract::State, Json};
 use axum_extra::extract::WithRejection;
-use chrono::Utc;
+use axum_extra::headers::{HeaderMapExt, IfMatch};
+use chrono::{NaiveDateTime as DateTime, Utc};
 use garde::Validate;
-use http::StatusCode;
+use http::{header, HeaderMap, StatusCode};
+use serde_json::Value;
 
+use entity::sea_orm_active_enums::{Priority, TodoEventKind};
+use entity::tags::Column as TagsColumn;
+use entity::tags::Entity as TagsEntity;
+use entity::todo_tags::Column as TodoTagsColumn;
+use entity::todo_tags::Entity as TodoTagsEntity;
 use entity::todos::ActiveModel as TodosActiveModel;
+use entity::todos::Column as TodosColumn;
 use entity::todos::Entity as TodosEntity;
-use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait};
+use entity::todos::Model as TodosModel;
+use sea_orm::sea_query::{
+    extension::postgres::PgExpr, Expr, IntoCondition, SelectStatement, SimpleExpr,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait,
+    FromQueryResult, LoaderTrait, QueryFilter, QueryOrder, QueryResult, QuerySelect, Select,
+    TransactionTrait,
+};
 
-use crate::dto::{TodoResponse, UpdateTodoRequest};
+use crate::auth::{authorize, visible_todos, CurrentUser, CurrentWorkspace};
+use crate::dto::{
+    Audit, BatchMode, BatchTodoOperation, BatchTodoResult, BatchTodosRequest, BatchTodosResponse,
+    Conditional, CustomMethodTarget, GetTodosQuery, Role, SearchTodosQuery, SortDirection,
+    TagMatch, TodoCursor, TodoResponse, TodoSearchResponse, TodoSearchResultResponse,
+    UpdateTodoRequest, Validated,
+};
+use crate::handler::{
+    comments::comment_counts,
+    history::{record_event, revert_todo_by_id},
+    lists::check_list_exists,
+    ordering::{last_rank, move_todo_by_id},
+    recurrence::{next_occurrence, recurrence_start, spawn_occurrence},
+    subtasks::{check_parent, detach_subtasks, subtask_rollups},
+    tags::set_todo_tags,
+};
 use crate::{
     dto::{NewTodoRequest, TodosResponse},
     error::{AppResult, ServiceError},
     server::AppState,
 };
 
+// Fragment of the body with the matches marked, the body is HTML escaped first so that only the
+// marks are markup and the snippet is safe to render
+const SEARCH_SNIPPET_SQL: &str = r#"
+ts_headline(
+    'english',
+    replace(replace(replace(replace(replace(body,
+        '&', '&amp;'), '<', '&lt;'), '>', '&gt;'), '"', '&quot;'), '''', '&#39;'),
+    websearch_to_tsquery('english', $1),
+    'StartSel=<mark>, StopSel=</mark>'
+)
+"#;
+
 #[utoipa::path(
     get,
     path = "/api/v1/todos",
     responses(
-        (status = 200, description = "get all todos", body = [TodosResponse]),
+        (status = 200, description = "get a page of filtered and sorted todos", body = [TodosResponse],
+            headers(("etag" = String, description = "hash of the page"), ("last-modified" = String))),
+        (status = 304, description = "page matches If-None-Match or is unchanged since If-Modified-Since"),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
         (status = 500, description = "database error", body = [ErrorResponse]),
-    )
+    ),
+    params(GetTodosQuery)
 )]
-pub async fn get_todos(State(state): State<AppState>) -> AppResult<TodosResponse> {
-    let res = TodosEntity::find().all(&*state.database).await?;
+pub async fn get_todos(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<GetTodosQuery>>,
+        ServiceError,
+    >,
+    headers: HeaderMap,
+) -> AppResult<Conditional<TodosResponse>> {
+    let txn = workspace.begin(&state).await?;
+    let todos = list_todos(&state, &txn, live_todos(workspace.id, user.id), query).await?;
+    txn.commit().await?;
+
+    Ok(Conditional::new(&headers, todos))
+}
+
+// Todos of the workspace visible to the user that are not in the trash, every lookup outside of
+// the trash starts from here
+pub(crate) fn live_todos(workspace_id: i64, user_id: i64) -> Select<TodosEntity> {
+    TodosEntity::find()
+        .filter(visible_todos(workspace_id, user_id))
+        .filter(TodosColumn::DeletedAt.is_null())
+}
 
-    let todos = TodosResponse {
-        todos: res.into_iter().map(|x| x.into()).collect(),
+// The trash is only ever seen and emptied by the owner of the todos in it
+pub(crate) fn trashed_todos(workspace_id: i64, owner_id: i64) -> Select<TodosEntity> {
+    TodosEntity::find()
+        .filter(TodosColumn::WorkspaceId.eq(workspace_id))
+        .filter(TodosColumn::OwnerId.eq(owner_id))
+        .filter(TodosColumn::DeletedAt.is_not_null())
+}
+
+pub(crate) async fn list_todos(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    select: Select<TodosEntity>,
+    query: GetTodosQuery,
+) -> AppResult<TodosResponse> {
+    let pagination = &state.config.pagination;
+    let limit = query
+        .limit
+        .unwrap_or(pagination.default_page_size)
+        .min(pagination.max_page_size);
+    let sort = query.sort.unwrap_or_default();
+    let column = TodosColumn::from(sort.field);
+
+    let mut select = select.filter(filter_condition(&query));
+    if sort.field.is_nullable() {
+        // todos without a value come last in both directions
+        select = select.order_by_asc(column.is_null());
+    }
+    select = select
+        .order_by(column, sort.direction.into())
+        .order_by(TodosColumn::Id, sort.direction.into());
+    if let Some(cursor) = &query.cursor {
+        let cursor = TodoCursor::decode(cursor, sort)?;
+        let key_is_null = cursor.key.is_null();
+        let (position, after): (Expr, SimpleExpr) = match key_is_null {
+            // past the last non-null value only the id orders the remaining todos
+            true => (Expr::col(TodosColumn::Id), Expr::val(cursor.id).into()),
+            false => (
+                Expr::tuple([Expr::col(column).into(), Expr::col(TodosColumn::Id).into()]),
+                Expr::tuple([cursor.key.into(), Expr::val(cursor.id).into()]).into(),
+            ),
+        };
+        let after = match sort.direction {
+            SortDirection::Asc => position.gt(after),
+            SortDirection::Desc => position.lt(after),
+        };
+        select = select.filter(match (sort.field.is_nullable(), key_is_null) {
+            (false, _) => Condition::all().add(after),
+            (true, false) => Condition::any().add(column.is_null()).add(after),
+            (true, true) => Condition::all().add(column.is_null()).add(after),
+        });
+    }
+
+    // fetch one extra row to know whether there is a next page
+    let mut res = select.limit(limit + 1).all(db).await?;
+    let has_more = res.len() as u64 > limit;
+    res.truncate(limit as usize);
+
+    let next_cursor = match has_more {
+        true => res.last().map(|todo| TodoCursor::new(sort, todo).encode()),
+        false => None,
     };
-    Ok(todos)
+
+    Ok(TodosResponse {
+        todos: todo_responses(db, res).await?,
+        next_cursor,
+        has_more,
+    })
+}
+
+fn filter_condition(query: &GetTodosQuery) -> Condition {
+    let mut condition = Condition::all();
+    if let Some(completed) = query.completed {
+        condition = condition.add(TodosColumn::Complated.eq(completed));
+    }
+    if let Some(created_after) = query.created_after {
+        condition = condition.add(TodosColumn::CreatedAt.gte(created_after));
+    }
+    if let Some(created_before) = query.created_before {
+        condition = condition.add(TodosColumn::CreatedAt.lt(created_before));
+    }
+    if let Some(updated_after) = query.updated_after {
+        condition = condition.add(TodosColumn::UpdatedAt.gte(updated_after));
+    }
+    if let Some(updated_before) = query.updated_before {
+        condition = condition.add(TodosColumn::UpdatedAt.lt(updated_before));
+    }
+    if let Some(priority) = query.priority {
+        condition = condition.add(TodosColumn::Priority.eq(Priority::from(priority)));
+    }
+    if let Some(due_after) = query.due_after {
+        condition = condition.add(TodosColumn::DueAt.gte(due_after));
+    }
+    if let Some(due_before) = query.due_before {
+        condition = condition.add(TodosColumn::DueAt.lt(due_before));
+    }
+    if let Some(completed_after) = query.completed_after {
+        condition = condition.add(TodosColumn::CompletedAt.gte(completed_after));
+    }
+    if let Some(completed_before) = query.completed_before {
+        condition = condition.add(TodosColumn::CompletedAt.lt(completed_before));
+    }
+    if let Some(body_contains) = &query.body_contains {
+        // match the input literally, backslash is the default escape character of LIKE
+        let escaped = body_contains
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        condition = condition.add(Expr::col(TodosColumn::Body).ilike(format!("%{escaped}%")));
+    }
+    if let Some(tag) = &query.tag {
+        let names: BTreeSet<&str> = tag
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        let mut tagged = SelectStatement::new();
+        tagged
+            .column((TodoTagsEntity, TodoTagsColumn::TodoId))
+            .from(TodoTagsEntity)
+            .inner_join(
+                TagsEntity,
+                Expr::col((TagsEntity, TagsColumn::Id))
+                    .equals((TodoTagsEntity, TodoTagsColumn::TagId)),
+            )
+            .and_where(Expr::col((TagsEntity, TagsColumn::Name)).is_in(names.iter().copied()));
+        if query.tag_match.unwrap_or_default() == TagMatch::All {
+            // a todo carries each tag at most once, so counting the matches is enough
+            tagged
+                .group_by_col((TodoTagsEntity, TodoTagsColumn::TodoId))
+                .and_having(
+                    Expr::col((TodoTagsEntity, TodoTagsColumn::TagId))
+                        .count()
+                        .eq(names.len() as i64),
+                );
+        }
+        condition = condition.add(TodosColumn::Id.in_subquery(tagged));
+    }
+    condition
+}
+
+pub(crate) async fn todo_response(
+    db: &impl ConnectionTrait,
+    todo: TodosModel,
+) -> AppResult<TodoResponse> {
+    let mut res = todo_responses(db, vec![todo]).await?;
+
+    // one todo in, one response out
+    Ok(res.remove(0))
+}
+
+pub(crate) async fn todo_responses(
+    db: &impl ConnectionTrait,
+    todos: Vec<TodosModel>,
+) -> AppResult<Vec<TodoResponse>> {
+    let tags = todos
+        .load_many_to_many(TagsEntity, TodoTagsEntity, db)
+        .await?;
+    let ids: Vec<i64> = todos.iter().map(|todo| todo.id).collect();
+    let mut rollups = subtask_rollups(db, &ids).await?;
+    let mut comment_counts = comment_counts(db, &ids).await?;
+
+    Ok(todos
+        .into_iter()
+        .zip(tags)
+        .map(|x| {
+            let mut res = TodoResponse::from(x);
+            res.subtasks = rollups.remove(&res.id).unwrap_or_default();
+            res.comment_count = comment_counts.remove(&res.id).unwrap_or_default();
+            res
+        })
+        .collect())
+}
+
+// Bumps the version of todos whose representation changed through another todo
+pub(crate) async fn touch_todos(
+    db: &impl ConnectionTrait,
+    condition: impl IntoCondition,
+) -> AppResult<()> {
+    TodosEntity::update_many()
+        .col_expr(TodosColumn::Version, Expr::col(TodosColumn::Version).add(1))
+        .col_expr(TodosColumn::UpdatedAt, Expr::value(Utc::now().naive_utc()))
+        .filter(condition)
+        .exec(db)
+        .await?;
+
+    Ok(())
 }
 
 #[utoipa::path(
@@ -46,26 +302,311 @@ pub async fn get_todos(State(state): State<AppState>) -> AppResult<TodosResponse
 )]
 pub async fn post_todos(
     State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    audit: Audit,
     WithRejection(Json(payload), _): WithRejection<Json<NewTodoRequest>, ServiceError>,
 ) -> AppResult<(StatusCode, TodoResponse)> {
+    let txn = workspace.begin(&state).await?;
+    let res = create_todo(&state, &txn, &audit, workspace.id, user.id, payload).await?;
+    let res = todo_response(&txn, res).await?;
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, res))
+}
+
+pub(crate) async fn create_todo(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    workspace_id: i64,
+    owner_id: i64,
+    payload: NewTodoRequest,
+) -> AppResult<TodosModel> {
     payload.validate(&())?;
+    check_list_exists(db, workspace_id, payload.list_id).await?;
+    check_parent(db, workspace_id, Some(owner_id), None, payload.parent_id).await?;
+    let rank = last_rank(state, db, workspace_id).await?;
 
+    let complated = payload.complated.unwrap_or_default();
     let todo = TodosActiveModel {
         body: ActiveValue::set(payload.body),
-        complated: ActiveValue::set(payload.complated.unwrap_or_default()),
+        complated: ActiveValue::set(complated),
+        due_at: ActiveValue::set(payload.due_at),
+        priority: ActiveValue::set(payload.priority.unwrap_or_default().into()),
+        completed_at: ActiveValue::set(completed_at(complated, None)),
+        list_id: ActiveValue::set(payload.list_id),
+        parent_id: ActiveValue::set(payload.parent_id),
+        rank: ActiveValue::set(rank),
+        recurrence_start: ActiveValue::set(recurrence_start(
+            &payload.recurrence,
+            payload.due_at,
+            None,
+        )),
+        recurrence: ActiveValue::set(payload.recurrence),
+        owner_id: ActiveValue::set(Some(owner_id)),
+        workspace_id: ActiveValue::set(workspace_id),
         ..Default::default()
     };
 
-    let res = todo.insert(&*state.database).await?;
+    let res = todo.insert(db).await?;
+    set_todo_tags(db, workspace_id, res.id, &payload.tags).await?;
+    if let Some(parent_id) = res.parent_id {
+        touch_todos(db, TodosColumn::Id.eq(parent_id)).await?;
+    }
+    let after = todo_response(db, res.clone()).await?;
+    record_event(db, audit, TodoEventKind::Created, None, Some(&after)).await?;
+
+    Ok(res)
+}
+
+// Custom methods share the `/v1/todos:{method}` route, see https://google.aip.dev/136
+pub async fn post_todos_custom_method(
+    State(state): State<AppState>,
+    Path(method): Path<String>,
+    request: Request,
+) -> Response {
+    match method.as_str() {
+        ":batch" => batch_todos.call(request, state).await,
+        _ => ServiceError::CustomMethodNotFoundError(method).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    request_body = BatchTodosRequest,
+    path = "/api/v1/todos:batch",
+    responses(
+        (status = 200, description = "operations committed, partial mode reports failures per operation", body = [BatchTodosResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = "4XX", description = "status of the failed operation in atomic mode, nothing is committed", body = [BatchTodosResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn batch_todos(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    audit: Audit,
+    WithRejection(Json(payload), _): WithRejection<Json<BatchTodosRequest>, ServiceError>,
+) -> AppResult<(StatusCode, BatchTodosResponse)> {
+    payload.validate(&())?;
+
+    let total = payload.operations.len();
+    let mut results = Vec::with_capacity(total);
+    let txn = workspace.begin(&state).await?;
+
+    for (index, operation) in payload.operations.into_iter().enumerate() {
+        match payload.mode {
+            BatchMode::Atomic => {
+                let res =
+                    run_batch_operation(&state, &txn, &audit, workspace.id, user.id, operation)
+                        .await;
+                match res {
+                    Ok(result) => results.push(result),
+                    Err(err) => {
+                        txn.rollback().await?;
+
+                        let status = err.get_status_code();
+                        let mut err = Some(err);
+                        let results = (0..total)
+                            .map(|i| match (i == index).then(|| err.take()).flatten() {
+                                Some(err) => err.into(),
+                                None => ServiceError::BatchAbortedError(index).into(),
+                            })
+                            .collect();
+
+                        return Ok((
+                            status,
+                            BatchTodosResponse {
+                                committed: false,
+                                results,
+                            },
+                        ));
+                    }
+                }
+            }
+            BatchMode::Partial => {
+                // a failed statement aborts the whole postgres transaction unless isolated by a savepoint
+                let savepoint = txn.begin().await?;
+                let res = run_batch_operation(
+                    &state,
+                    &savepoint,
+                    &audit,
+                    workspace.id,
+                    user.id,
+                    operation,
+                )
+                .await;
+                match res {
+                    Ok(result) => {
+                        savepoint.commit().await?;
+                        results.push(result);
+                    }
+                    Err(err) => {
+                        savepoint.rollback().await?;
+                        results.push(err.into());
+                    }
+                }
+            }
+        }
+    }
+
+    txn.commit().await?;
+
+    Ok((
+        StatusCode::OK,
+        BatchTodosResponse {
+            committed: true,
+            results,
+        },
+    ))
+}
+
+async fn run_batch_operation(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    workspace_id: i64,
+    user_id: i64,
+    operation: BatchTodoOperation,
+) -> AppResult<BatchTodoResult> {
+    let if_match = |version: Option<i32>| version.map(|v| TodoResponse::version_etag(v).into());
+
+    match operation {
+        BatchTodoOperation::Create(payload) => {
+            let res = create_todo(state, db, audit, workspace_id, user_id, payload).await?;
+
+            Ok(BatchTodoResult::success(
+                StatusCode::CREATED,
+                Some(todo_response(db, res).await?),
+            ))
+        }
+        BatchTodoOperation::Update { id, version, todo } => {
+            todo.validate(&())?;
+
+            let res = find_live_todo(db, workspace_id, user_id, id, Role::Editor).await?;
+            check_if_match(state, if_match(version), id, res.as_ref())?;
+            let res = res.ok_or(ServiceError::TodoNotFoundError(id))?;
+
+            let res = replace_todo(state, db, audit, TodoEventKind::Updated, res, todo).await?;
+
+            Ok(BatchTodoResult::success(
+                StatusCode::OK,
+                Some(todo_response(db, res).await?),
+            ))
+        }
+        BatchTodoOperation::Delete { id, version } => {
+            let res = find_live_todo(db, workspace_id, user_id, id, Role::Editor).await?;
+            check_if_match(state, if_match(version), id, res.as_ref())?;
+
+            match res {
+                Some(todo) => {
+                    trash_todo(db, audit, todo).await?;
+                    Ok(BatchTodoResult::success(StatusCode::OK, None))
+                }
+                None => Ok(BatchTodoResult::success(StatusCode::NO_CONTENT, None)),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TodoSearchRow {
+    todo: TodosModel,
+    rank: f32,
+    snippet: String,
+}
+
+impl FromQueryResult for TodoSearchRow {
+    fn from_query_result(res: &QueryResult, pre: &str) -> Result<Self, DbErr> {
+        Ok(Self {
+            todo: TodosModel::from_query_result(res, pre)?,
+            rank: res.try_get(pre, "search_rank")?,
+            snippet: res.try_get(pre, "snippet")?,
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/todos/search",
+    responses(
+        (status = 200, description = "search todos by body, best matches first", body = [TodoSearchResponse]),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(SearchTodosQuery)
+)]
+pub async fn search_todos(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<SearchTodosQuery>>,
+        ServiceError,
+    >,
+) -> AppResult<TodoSearchResponse> {
+    let pagination = &state.config.pagination;
+    let limit = query
+        .limit
+        .unwrap_or(pagination.default_page_size)
+        .min(pagination.max_page_size);
+
+    let txn = workspace.begin(&state).await?;
+
+    // `body_tsv` is generated by postgres from `body` with the same text search configuration
+    let res = live_todos(workspace.id, user.id)
+        .column_as(
+            Expr::cust_with_values(
+                "ts_rank(body_tsv, websearch_to_tsquery('english', $1))",
+                [query.q.clone()],
+            ),
+            "search_rank",
+        )
+        .column_as(
+            Expr::cust_with_values(SEARCH_SNIPPET_SQL, [query.q.clone()]),
+            "snippet",
+        )
+        .filter(Expr::cust_with_values(
+            "body_tsv @@ websearch_to_tsquery('english', $1)",
+            [query.q],
+        ))
+        .order_by_desc(Expr::cust("search_rank"))
+        .order_by_asc(TodosColumn::Id)
+        .limit(limit)
+        .into_model::<TodoSearchRow>()
+        .all(&txn)
+        .await?;
+
+    let (todos, matches): (Vec<_>, Vec<_>) = res
+        .into_iter()
+        .map(|row| (row.todo, (row.rank, row.snippet)))
+        .unzip();
+    let todos = todo_responses(&txn, todos).await?;
+    txn.commit().await?;
 
-    Ok((StatusCode::CREATED, res.into()))
+    Ok(TodoSearchResponse {
+        results: todos
+            .into_iter()
+            .zip(matches)
+            .map(|(todo, (rank, snippet))| TodoSearchResultResponse {
+                todo,
+                rank,
+                snippet,
+            })
+            .collect(),
+    })
 }
 
 #[utoipa::path(
     get,
     path = "/api/v1/todos/{id}",
     responses(
-        (status = 200, description = "get todo", body = [TodoResponse]),
+        (status = 200, description = "get todo", body = [TodoResponse],
+            headers(("etag" = String, description = "current version of the todo"), ("last-modified" = String))),
+        (status = 304, description = "todo matches If-None-Match or is unchanged since If-Modified-Since"),
         (status = 404, description = "todo not found", body = [ErrorResponse]),
         (status = 500, description = "database error", body = [ErrorResponse]),
     ),
@@ -75,14 +616,21 @@ pub async fn post_todos(
 )]
 pub async fn get_todo_by_id(
     State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
     WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
-) -> AppResult<TodoResponse> {
-    let res = TodosEntity::find_by_id(id).one(&*state.database).await?;
+    headers: HeaderMap,
+) -> AppResult<Conditional<TodoResponse>> {
+    let txn = workspace.begin(&state).await?;
 
-    match res {
-        Some(todo) => Ok(todo.into()),
-        None => Err(ServiceError::TodoNotFoundError(id)),
-    }
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Viewer)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    let res = todo_response(&txn, todo).await?;
+
+    txn.commit().await?;
+
+    Ok(Conditional::new(&headers, res))
 }
 
 #[utoipa::path(
@@ -90,10 +638,14 @@ pub async fn get_todo_by_id(
     request_body = UpdateTodoRequest,
     path = "/api/v1/todos/{id}",
     responses(
-        (status = 200, description = "update todo", body = [TodoResponse]),
-        (status = 201, description = "create todo", body = [ErrorResponse]),
+        (status = 200, description = "replace todo", body = [TodoResponse]),
         (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 403, description = "todo is only shared with the user for viewing", body = [ErrorResponse]),
+        (status = 404, description = "todo not found, new todos are created with POST", body = [ErrorResponse]),
+        (status = 409, description = "todo is in the trash", body = [ErrorResponse]),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
         (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
         (status = 500, description = "database error", body = [ErrorResponse]),
     ),
     params(
@@ -102,50 +654,299 @@ pub async fn get_todo_by_id(
 )]
 pub async fn put_todo_by_id(
     State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
     WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
     WithRejection(Json(payload), _): WithRejection<Json<UpdateTodoRequest>, ServiceError>,
-) -> AppResult<(StatusCode, TodoResponse)> {
+) -> AppResult<TodoResponse> {
+    payload.validate(&())?;
+
+    let txn = workspace.begin(&state).await?;
+
+    // todos are only created through POST, which picks their id, so a PUT cannot tell which
+    // ids are taken in other workspaces
+    let todo = TodosEntity::find_by_id(id)
+        .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    authorize(&txn, user.id, &todo, Role::Editor).await?;
+    if todo.deleted_at.is_some() {
+        return Err(ServiceError::TodoTrashedError(id));
+    }
+    check_if_match(&state, headers.typed_get(), id, Some(&todo))?;
+
+    let res = replace_todo(&state, &txn, &audit, TodoEventKind::Updated, todo, payload).await?;
+    let res = todo_response(&txn, res).await?;
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+#[utoipa::path(
+    patch,
+    request_body(
+        content = Object,
+        content_type = "application/merge-patch+json",
+        description = "RFC 7396 merge patch, or a RFC 6902 json patch sent as application/json-patch+json",
+    ),
+    path = "/api/v1/todos/{id}",
+    responses(
+        (status = 200, description = "patch todo", body = [TodoResponse]),
+        (status = 400, description = "invalid request or patch document", body = [ErrorResponse]),
+        (status = 403, description = "todo is only shared with the user for viewing", body = [ErrorResponse]),
+        (status = 404, description = "todo not found", body = [ErrorResponse]),
+        (status = 409, description = "patch cannot be applied", body = [ErrorResponse]),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
+        (status = 415, description = "unsupported patch media type", body = [ErrorResponse]),
+        (status = 422, description = "patch modifies read-only fields", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to get todo for"),
+    )
+)]
+pub async fn patch_todo_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
+    patch: Bytes,
+) -> AppResult<TodoResponse> {
+    let txn = workspace.begin(&state).await?;
+
+    let todo = find_live_todo(&txn, workspace.id, user.id, id, Role::Editor)
+        .await?
+        .ok_or(ServiceError::TodoNotFoundError(id))?;
+    check_if_match(&state, headers.typed_get(), id, Some(&todo))?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    // the patch applies to the representation clients see, not to the database row
+    let original = serde_json::to_value(todo_response(&txn, todo.clone()).await?)
+        .map_err(|err| DbErr::Json(err.to_string()))?;
+    let mut document = original.clone();
+    apply_patch(&mut document, content_type, &patch)?;
+
+    // a patch only changes the fields of the todo, it cannot add new ones
+    if let Some(field) = document
+        .as_object()
+        .and_then(|fields| fields.keys().find(|field| original.get(field).is_none()))
+    {
+        return Err(ServiceError::InvalidPatchError(serde::de::Error::custom(
+            format!("unknown field `{field}`"),
+        )));
+    }
+
+    for field in [
+        "id",
+        "created_at",
+        "updated_at",
+        "version",
+        "deleted_at",
+        "completed_at",
+        "subtasks",
+        "comment_count",
+        "owner_id",
+        "workspace_id",
+    ] {
+        if original.get(field) != document.get(field) {
+            return Err(ServiceError::ReadOnlyFieldError(field.to_owned()));
+        }
+    }
+
+    let payload: UpdateTodoRequest =
+        serde_json::from_value(document).map_err(ServiceError::InvalidPatchError)?;
     payload.validate(&())?;
 
-    let res = TodosEntity::find_by_id(id).one(&*state.database).await?;
-    let mut todo: TodosActiveModel = match res {
-        Some(todo) => todo.into(),
-        None => {
-            let payload: NewTodoRequest = payload.into();
-            payload.validate(&())?;
+    let res = replace_todo(&state, &txn, &audit, TodoEventKind::Updated, todo, payload).await?;
+    let res = todo_response(&txn, res).await?;
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+fn apply_patch(document: &mut Value, content_type: &str, patch: &[u8]) -> AppResult<()> {
+    // parameters such as charset do not change how the patch is interpreted
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
 
-            let todo = TodosActiveModel {
-                id: ActiveValue::set(id),
-                body: ActiveValue::set(payload.body),
-                complated: ActiveValue::set(payload.complated.unwrap_or_default()),
-                ..Default::default()
-            };
+    if media_type.eq_ignore_ascii_case("application/merge-patch+json") {
+        let patch: Value =
+            serde_json::from_slice(patch).map_err(ServiceError::InvalidPatchError)?;
+        json_patch::merge(document, &patch);
+    } else if media_type.eq_ignore_ascii_case("application/json-patch+json") {
+        let patch: json_patch::Patch =
+            serde_json::from_slice(patch).map_err(ServiceError::InvalidPatchError)?;
+        json_patch::patch(document, &patch)?;
+    } else {
+        return Err(ServiceError::UnsupportedPatchMediaTypeError(
+            content_type.to_owned(),
+        ));
+    }
+
+    Ok(())
+}
+
+// A todo of the workspace the user holds at least `role` on, todos they cannot see at all are
+// not found
+pub(crate) async fn find_live_todo(
+    db: &impl ConnectionTrait,
+    workspace_id: i64,
+    user_id: i64,
+    id: i64,
+    role: Role,
+) -> AppResult<Option<TodosModel>> {
+    let res = live_todos(workspace_id, user_id)
+        .filter(TodosColumn::Id.eq(id))
+        .one(db)
+        .await?;
+    if let Some(todo) = &res {
+        authorize(db, user_id, todo, role).await?;
+    }
 
-            let res = todo.insert(&*state.database).await?;
+    Ok(res)
+}
 
-            return Ok((StatusCode::CREATED, res.into()));
+// `If-Match` is evaluated against the current version, a missing todo never matches
+pub(crate) fn check_if_match(
+    state: &AppState,
+    if_match: Option<IfMatch>,
+    id: i64,
+    todo: Option<&TodosModel>,
+) -> AppResult<()> {
+    match (if_match, todo) {
+        (None, Some(_)) if state.config.precondition.require_if_match => {
+            Err(ServiceError::PreconditionRequiredError(id))
+        }
+        (None, _) => Ok(()),
+        (Some(if_match), Some(todo))
+            if if_match.precondition_passes(&TodoResponse::version_etag(todo.version)) =>
+        {
+            Ok(())
         }
+        (Some(_), _) => Err(ServiceError::PreconditionFailedError(id)),
+    }
+}
+
+pub(crate) async fn replace_todo(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    kind: TodoEventKind,
+    todo: TodosModel,
+    payload: UpdateTodoRequest,
+) -> AppResult<TodosModel> {
+    let before = todo_response(db, todo.clone()).await?;
+    let completed_at = completed_at(payload.complated, Some(&todo));
+    let recurrence_start = recurrence_start(&payload.recurrence, payload.due_at, Some(&todo));
+    // completing a recurring todo hands its rule over to the next occurrence
+    let next_due_at = match payload.complated && !todo.complated {
+        true => next_occurrence(&payload.recurrence, recurrence_start, payload.due_at)?,
+        false => None,
     };
+    let handover = next_due_at.is_some();
+
+    let workspace_id = todo.workspace_id;
+    check_list_exists(db, workspace_id, payload.list_id).await?;
+    check_parent(
+        db,
+        workspace_id,
+        todo.owner_id,
+        Some(todo.id),
+        payload.parent_id,
+    )
+    .await?;
+    set_todo_tags(db, workspace_id, todo.id, &payload.tags).await?;
+    let res = update_todo(db, todo, |todo| {
+        todo.body = ActiveValue::set(payload.body);
+        todo.complated = ActiveValue::set(payload.complated);
+        todo.due_at = ActiveValue::set(payload.due_at);
+        todo.priority = ActiveValue::set(payload.priority.into());
+        todo.list_id = ActiveValue::set(payload.list_id);
+        todo.parent_id = ActiveValue::set(payload.parent_id);
+        todo.completed_at = ActiveValue::set(completed_at);
+        todo.recurrence = ActiveValue::set(payload.recurrence.clone().filter(|_| !handover));
+        todo.recurrence_start = ActiveValue::set(recurrence_start.filter(|_| !handover));
+        todo.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    })
+    .await?;
+    let after = todo_response(db, res.clone()).await?;
+    record_event(db, audit, kind, Some(&before), Some(&after)).await?;
 
-    if let Some(body) = payload.body {
-        todo.body = ActiveValue::set(body);
+    if let (Some(due_at), Some(rule), Some(start)) =
+        (next_due_at, payload.recurrence, recurrence_start)
+    {
+        let occurrence =
+            spawn_occurrence(state, db, &res, &payload.tags, (rule, start), due_at).await?;
+        let occurrence = todo_response(db, occurrence).await?;
+        record_event(db, audit, TodoEventKind::Created, None, Some(&occurrence)).await?;
+        // making room for the occurrence may have respaced the ranks, which bumps the version
+        return Ok(TodosEntity::find_by_id(res.id)
+            .one(db)
+            .await?
+            .unwrap_or(res));
     }
-    if let Some(complated) = payload.complated {
-        todo.complated = ActiveValue::set(complated);
+
+    Ok(res)
+}
+
+// `completed_at` follows `complated`, keeping the original time while the todo stays complated
+fn completed_at(complated: bool, todo: Option<&TodosModel>) -> Option<DateTime> {
+    match (complated, todo) {
+        (false, _) => None,
+        (true, Some(todo)) if todo.complated && todo.completed_at.is_some() => todo.completed_at,
+        (true, _) => Some(Utc::now().naive_utc()),
     }
-    todo.updated_at = ActiveValue::set(Utc::now().naive_utc());
+}
+
+// Applies `change` and bumps the version, failing if the todo changed since it was read
+pub(crate) async fn update_todo(
+    db: &impl ConnectionTrait,
+    todo: TodosModel,
+    change: impl FnOnce(&mut TodosActiveModel),
+) -> AppResult<TodosModel> {
+    let (id, version) = (todo.id, todo.version);
+    let rollup = |todo: &TodosModel| (todo.parent_id, todo.complated, todo.deleted_at.is_some());
+    let before = rollup(&todo);
+
+    let mut todo: TodosActiveModel = todo.into();
+    change(&mut todo);
+    todo.version = ActiveValue::set(version + 1);
 
-    let res = todo.update(&*state.database).await?;
+    let res = TodosEntity::update(todo)
+        .filter(TodosColumn::Version.eq(version))
+        .exec(db)
+        .await
+        .map_err(|err| match err {
+            DbErr::RecordNotUpdated => ServiceError::PreconditionFailedError(id),
+            err => err.into(),
+        })?;
 
-    Ok((StatusCode::OK, res.into()))
+    // the subtask rollup of the old and the new parent may have changed
+    let parents: Vec<i64> = before.0.into_iter().chain(res.parent_id).collect();
+    if rollup(&res) != before && !parents.is_empty() {
+        touch_todos(db, TodosColumn::Id.is_in(parents)).await?;
+    }
+
+    Ok(res)
 }
 
 #[utoipa::path(
     delete,
     path = "/api/v1/todos/{id}",
     responses(
-        (status = 200, description = "delete todo"),
+        (status = 200, description = "move todo to the trash"),
         (status = 204, description = "todo not found"),
+        (status = 403, description = "todo is only shared with the user for viewing", body = [ErrorResponse]),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
         (status = 500, description = "database error", body = [ErrorResponse]),
     ),
     params(
@@ -154,13 +955,145 @@ pub async fn put_todo_by_id(
 )]
 pub async fn delete_todo_by_id(
     State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
     WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
 ) -> AppResult<StatusCode> {
-    let res = TodosEntity::delete_by_id(id).exec(&*state.database).await?;
+    let txn = workspace.begin(&state).await?;
 
-    if res.rows_affected == 0 {
-        Ok(StatusCode::NO_CONTENT)
-    } else {
-        Ok(StatusCode::OK)
+    let res = find_live_todo(&txn, workspace.id, user.id, id, Role::Editor).await?;
+    check_if_match(&state, headers.typed_get(), id, res.as_ref())?;
+
+    match res {
+        Some(todo) => {
+            trash_todo(&txn, &audit, todo).await?;
+            txn.commit().await?;
+            Ok(StatusCode::OK)
+        }
+        None => Ok(StatusCode::NO_CONTENT),
+    }
+}
+
+pub(crate) async fn trash_todo(
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    todo: TodosModel,
+) -> AppResult<TodosModel> {
+    let before = todo_response(db, todo.clone()).await?;
+    let now = Utc::now().naive_utc();
+    let res = update_todo(db, todo, |todo| {
+        todo.deleted_at = ActiveValue::set(Some(now));
+        todo.updated_at = ActiveValue::set(now);
+    })
+    .await?;
+
+    let after = todo_response(db, res.clone()).await?;
+    record_event(
+        db,
+        audit,
+        TodoEventKind::Trashed,
+        Some(&before),
+        Some(&after),
+    )
+    .await?;
+
+    Ok(res)
+}
+
+// Custom methods on a single todo share the `/v1/todos/{id}` route with `{id}:{method}`
+pub async fn post_todo_custom_method(
+    State(state): State<AppState>,
+    WithRejection(Path(target), _): WithRejection<Path<CustomMethodTarget>, ServiceError>,
+    request: Request,
+) -> Response {
+    match target.method.as_str() {
+        "restore" => restore_todo_by_id.call(request, state).await,
+        "move" => move_todo_by_id.call(request, state).await,
+        "revert" => revert_todo_by_id.call(request, state).await,
+        _ => ServiceError::CustomMethodNotFoundError(target.method).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/todos/{id}:restore",
+    responses(
+        (status = 200, description = "move todo out of the trash", body = [TodoResponse]),
+        (status = 403, description = "todo is only shared with the user for viewing", body = [ErrorResponse]),
+        (status = 404, description = "todo not found in the trash", body = [ErrorResponse]),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to restore"),
+    )
+)]
+pub async fn restore_todo_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(target), _): WithRejection<Path<CustomMethodTarget>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> AppResult<TodoResponse> {
+    let id = target.id;
+    let txn = workspace.begin(&state).await?;
+
+    // editors may restore what they trashed, though only the owner sees it in the trash
+    let todo = TodosEntity::find_by_id(id)
+        .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+        .filter(TodosColumn::DeletedAt.is_not_null())
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::TrashedTodoNotFoundError(id))?;
+    authorize(&txn, user.id, &todo, Role::Editor)
+        .await
+        .map_err(|err| match err {
+            ServiceError::TodoNotFoundError(id) => ServiceError::TrashedTodoNotFoundError(id),
+            err => err,
+        })?;
+    check_if_match(&state, headers.typed_get(), id, Some(&todo))?;
+
+    let before = todo_response(&txn, todo.clone()).await?;
+    let res = update_todo(&txn, todo, |todo| {
+        todo.deleted_at = ActiveValue::set(None);
+        todo.updated_at = ActiveValue::set(Utc::now().naive_utc());
+    })
+    .await?;
+    let res = todo_response(&txn, res).await?;
+    record_event(
+        &txn,
+        &audit,
+        TodoEventKind::Restored,
+        Some(&before),
+        Some(&res),
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+pub(crate) async fn delete_todo(
+    db: &impl ConnectionTrait,
+    audit: &Audit,
+    todo: &TodosModel,
+) -> AppResult<()> {
+    let before = todo_response(db, todo.clone()).await?;
+    detach_subtasks(db, TodosColumn::ParentId.eq(todo.id)).await?;
+
+    // the version guards against changes made since the todo was read
+    let res = TodosEntity::delete_by_id(todo.id)
+        .filter(TodosColumn::Version.eq(todo.version))
+        .exec(db)
+        .await?;
+
+    match res.rows_affected {
+        0 => Err(ServiceError::PreconditionFailedError(todo.id)),
+        _ => record_event(db, audit, TodoEventKind::Purged, Some(&before), None).await,
     }
 }
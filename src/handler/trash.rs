@@ -0,0 +1,137 @@
+use axum::extract::{Path, Query, State};
+use axum_extra::extract::WithRejection;
+use axum_extra::headers::HeaderMapExt;
+use http::{HeaderMap, StatusCode};
+
+use entity::attachments::Column as AttachmentsColumn;
+use entity::sea_orm_active_enums::TodoEventKind;
+use entity::todos::Column as TodosColumn;
+use entity::todos::Entity as TodosEntity;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect, QueryTrait};
+
+use crate::auth::{CurrentUser, CurrentWorkspace};
+use crate::handler::attachments::{attachment_keys, discard_blobs};
+use crate::handler::history::record_event;
+use crate::handler::subtasks::detach_subtasks;
+use crate::handler::todos::{
+    check_if_match, delete_todo, list_todos, todo_responses, trashed_todos,
+};
+use crate::{
+    dto::{Audit, Conditional, GetTodosQuery, TodosResponse, Validated},
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trash",
+    responses(
+        (status = 200, description = "get a page of filtered and sorted todos in the trash", body = [TodosResponse],
+            headers(("etag" = String, description = "hash of the page"), ("last-modified" = String))),
+        (status = 304, description = "page matches If-None-Match or is unchanged since If-Modified-Since"),
+        (status = 400, description = "invalid query", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(GetTodosQuery)
+)]
+pub async fn get_trash(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Query(Validated(query)), _): WithRejection<
+        Query<Validated<GetTodosQuery>>,
+        ServiceError,
+    >,
+    headers: HeaderMap,
+) -> AppResult<Conditional<TodosResponse>> {
+    let txn = workspace.begin(&state).await?;
+    let todos = list_todos(&state, &txn, trashed_todos(workspace.id, user.id), query).await?;
+    txn.commit().await?;
+
+    Ok(Conditional::new(&headers, todos))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/trash",
+    responses(
+        (status = 204, description = "permanently delete every todo in the trash"),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn purge_trash(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    audit: Audit,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    let purged =
+        todo_responses(&txn, trashed_todos(workspace.id, user.id).all(&txn).await?).await?;
+
+    let trashed = trashed_todos(workspace.id, user.id)
+        .select_only()
+        .column(TodosColumn::Id)
+        .into_query();
+    let blobs =
+        attachment_keys(&txn, AttachmentsColumn::TodoId.in_subquery(trashed.clone())).await?;
+    detach_subtasks(&txn, TodosColumn::ParentId.in_subquery(trashed)).await?;
+    TodosEntity::delete_many()
+        .filter(TodosColumn::WorkspaceId.eq(workspace.id))
+        .filter(TodosColumn::OwnerId.eq(user.id))
+        .filter(TodosColumn::DeletedAt.is_not_null())
+        .exec(&txn)
+        .await?;
+    for todo in &purged {
+        record_event(&txn, &audit, TodoEventKind::Purged, Some(todo), None).await?;
+    }
+
+    txn.commit().await?;
+
+    discard_blobs(&*state.blobs, &blobs).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/trash/{id}",
+    responses(
+        (status = 200, description = "permanently delete todo from the trash"),
+        (status = 204, description = "todo not found in the trash"),
+        (status = 412, description = "todo does not match If-Match", body = [ErrorResponse]),
+        (status = 428, description = "If-Match is required", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "todo database id to purge"),
+    )
+)]
+pub async fn purge_trashed_todo_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    workspace: CurrentWorkspace,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    headers: HeaderMap,
+    audit: Audit,
+) -> AppResult<StatusCode> {
+    let txn = workspace.begin(&state).await?;
+
+    let res = trashed_todos(workspace.id, user.id)
+        .filter(TodosColumn::Id.eq(id))
+        .one(&txn)
+        .await?;
+    check_if_match(&state, headers.typed_get(), id, res.as_ref())?;
+
+    match res {
+        Some(todo) => {
+            let blobs = attachment_keys(&txn, AttachmentsColumn::TodoId.eq(id)).await?;
+            delete_todo(&txn, &audit, &todo).await?;
+            txn.commit().await?;
+            discard_blobs(&*state.blobs, &blobs).await;
+            Ok(StatusCode::OK)
+        }
+        None => Ok(StatusCode::NO_CONTENT),
+    }
+}
@@ -0,0 +1,345 @@
+use axum::extract::{Path, State};
+use axum::Json;
+use axum_extra::extract::WithRejection;
+use garde::Validate;
+use http::StatusCode;
+
+use entity::users::Column as UsersColumn;
+use entity::users::Entity as UsersEntity;
+use entity::workspace_members::ActiveModel as WorkspaceMembersActiveModel;
+use entity::workspace_members::Column as WorkspaceMembersColumn;
+use entity::workspace_members::Entity as WorkspaceMembersEntity;
+use entity::workspaces::ActiveModel as WorkspacesActiveModel;
+use entity::workspaces::Column as WorkspacesColumn;
+use entity::workspaces::Entity as WorkspacesEntity;
+use entity::workspaces::Model as WorkspacesModel;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, SqlErr, TransactionTrait,
+};
+
+use crate::auth::CurrentUser;
+use crate::{
+    dto::{
+        MemberRequest, MemberResponse, MembersResponse, WorkspaceRequest, WorkspaceResponse,
+        WorkspacesResponse,
+    },
+    error::{AppResult, ServiceError},
+    server::AppState,
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/workspaces",
+    responses(
+        (status = 200, description = "get the workspaces of the user, only the one credentials are limited to", body = [WorkspacesResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn get_workspaces(
+    State(state): State<AppState>,
+    user: CurrentUser,
+) -> AppResult<WorkspacesResponse> {
+    let mut select = WorkspaceMembersEntity::find()
+        .filter(WorkspaceMembersColumn::UserId.eq(user.id))
+        .find_also_related(WorkspacesEntity);
+    if let Some(workspace_id) = user.workspace_id {
+        select = select.filter(WorkspacesColumn::Id.eq(workspace_id));
+    }
+    let res = select
+        .order_by_asc(WorkspacesColumn::Slug)
+        .all(&*state.database)
+        .await?;
+
+    Ok(WorkspacesResponse {
+        workspaces: res
+            .into_iter()
+            .filter_map(|(member, workspace)| workspace.map(|w| (w, member.admin).into()))
+            .collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = WorkspaceRequest,
+    path = "/api/v1/workspaces",
+    responses(
+        (status = 201, description = "create a workspace with the user as its admin", body = [WorkspaceResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 409, description = "slug is taken", body = [ErrorResponse]),
+        (status = 422, description = "lack of necessary fields", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    )
+)]
+pub async fn post_workspaces(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Json(payload), _): WithRejection<Json<WorkspaceRequest>, ServiceError>,
+) -> AppResult<(StatusCode, WorkspaceResponse)> {
+    payload.validate(&())?;
+
+    let txn = state.database.begin().await?;
+
+    let slug = payload.slug;
+    let workspace = WorkspacesActiveModel {
+        slug: ActiveValue::set(slug.clone()),
+        name: ActiveValue::set(payload.name.trim().to_owned()),
+        ..Default::default()
+    };
+    let res = workspace
+        .insert(&txn)
+        .await
+        .map_err(|err| match err.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                ServiceError::WorkspaceSlugTakenError(slug)
+            }
+            _ => err.into(),
+        })?;
+    WorkspaceMembersActiveModel {
+        workspace_id: ActiveValue::set(res.id),
+        user_id: ActiveValue::set(user.id),
+        admin: ActiveValue::set(true),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok((StatusCode::CREATED, (res, true).into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/workspaces/{id}",
+    responses(
+        (status = 200, description = "get workspace by id", body = [WorkspaceResponse]),
+        (status = 404, description = "workspace not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "workspace database id"),
+    )
+)]
+pub async fn get_workspace_by_id(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<WorkspaceResponse> {
+    let res = find_membership(&*state.database, &user, id).await?;
+
+    Ok(res.into())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/workspaces/{id}/members",
+    responses(
+        (status = 200, description = "get the members of a workspace", body = [MembersResponse]),
+        (status = 404, description = "workspace not found", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "workspace database id"),
+    )
+)]
+pub async fn get_workspace_members(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+) -> AppResult<MembersResponse> {
+    find_membership(&*state.database, &user, id).await?;
+
+    let res = WorkspaceMembersEntity::find()
+        .filter(WorkspaceMembersColumn::WorkspaceId.eq(id))
+        .find_also_related(UsersEntity)
+        .order_by_asc(UsersColumn::Email)
+        .all(&*state.database)
+        .await?;
+
+    Ok(MembersResponse {
+        members: res
+            .into_iter()
+            .filter_map(|(member, user)| user.map(|user| (member, user).into()))
+            .collect(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    request_body = MemberRequest,
+    path = "/api/v1/workspaces/{id}/members",
+    responses(
+        (status = 200, description = "change whether a member is an admin", body = [MemberResponse]),
+        (status = 201, description = "add a member", body = [MemberResponse]),
+        (status = 400, description = "invalid request", body = [ErrorResponse]),
+        (status = 403, description = "user is no admin of the workspace", body = [ErrorResponse]),
+        (status = 404, description = "workspace not found", body = [ErrorResponse]),
+        (status = 422, description = "unknown user, or the last admin would be lost", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "workspace database id"),
+    )
+)]
+pub async fn post_workspace_members(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path(id), _): WithRejection<Path<i64>, ServiceError>,
+    WithRejection(Json(payload), _): WithRejection<Json<MemberRequest>, ServiceError>,
+) -> AppResult<(StatusCode, MemberResponse)> {
+    payload.validate(&())?;
+
+    let txn = state.database.begin().await?;
+
+    let (_, admin) = find_membership(&txn, &user, id).await?;
+    if !admin {
+        return Err(ServiceError::WorkspaceAdminRequiredError(id));
+    }
+
+    let email = payload.email.to_lowercase();
+    let member_user = UsersEntity::find()
+        .filter(UsersColumn::Email.eq(&email))
+        .one(&txn)
+        .await?
+        .ok_or(ServiceError::UnknownMemberError(email))?;
+
+    let existing = WorkspaceMembersEntity::find_by_id((id, member_user.id))
+        .one(&txn)
+        .await?;
+    let (status, res) = match existing {
+        Some(member) => {
+            if member.admin && !payload.admin {
+                check_other_admins(&txn, id, member.user_id).await?;
+            }
+            let mut member: WorkspaceMembersActiveModel = member.into();
+            member.admin = ActiveValue::set(payload.admin);
+            (StatusCode::OK, member.update(&txn).await?)
+        }
+        None => {
+            let member = WorkspaceMembersActiveModel {
+                workspace_id: ActiveValue::set(id),
+                user_id: ActiveValue::set(member_user.id),
+                admin: ActiveValue::set(payload.admin),
+                ..Default::default()
+            };
+            (StatusCode::CREATED, member.insert(&txn).await?)
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok((status, (res, member_user).into()))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/workspaces/{id}/members/{user_id}",
+    responses(
+        (status = 200, description = "remove a member, either by an admin or by the member themselves, their todos stay in the workspace"),
+        (status = 204, description = "member not found"),
+        (status = 403, description = "user is no admin of the workspace", body = [ErrorResponse]),
+        (status = 404, description = "workspace not found", body = [ErrorResponse]),
+        (status = 422, description = "the last admin would be lost", body = [ErrorResponse]),
+        (status = 500, description = "database error", body = [ErrorResponse]),
+    ),
+    params(
+        ("id" = u64, Path, description = "workspace database id"),
+        ("user_id" = u64, Path, description = "user database id of the member to remove"),
+    )
+)]
+pub async fn delete_workspace_member(
+    State(state): State<AppState>,
+    user: CurrentUser,
+    WithRejection(Path((id, user_id)), _): WithRejection<Path<(i64, i64)>, ServiceError>,
+) -> AppResult<StatusCode> {
+    let txn = state.database.begin().await?;
+
+    let (_, admin) = find_membership(&txn, &user, id).await?;
+    if !admin && user_id != user.id {
+        return Err(ServiceError::WorkspaceAdminRequiredError(id));
+    }
+
+    let member = WorkspaceMembersEntity::find_by_id((id, user_id))
+        .one(&txn)
+        .await?;
+    let res = match member {
+        Some(member) => {
+            if member.admin {
+                check_other_admins(&txn, id, user_id).await?;
+            }
+            WorkspaceMembersEntity::delete_by_id((id, user_id))
+                .exec(&txn)
+                .await?;
+            StatusCode::OK
+        }
+        None => StatusCode::NO_CONTENT,
+    };
+
+    txn.commit().await?;
+
+    Ok(res)
+}
+
+// New accounts start out in the default workspace unless turned off
+pub(crate) async fn join_default_workspace(
+    state: &AppState,
+    db: &impl ConnectionTrait,
+    user_id: i64,
+) -> AppResult<()> {
+    let tenancy = &state.config.tenancy;
+    if !tenancy.join_default_workspace {
+        return Ok(());
+    }
+
+    let workspace = WorkspacesEntity::find()
+        .filter(WorkspacesColumn::Slug.eq(&tenancy.default_workspace))
+        .one(db)
+        .await?;
+    if let Some(workspace) = workspace {
+        WorkspaceMembersActiveModel {
+            workspace_id: ActiveValue::set(workspace.id),
+            user_id: ActiveValue::set(user_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Workspaces the user is no member of, or their credentials are not limited to, do not exist
+async fn find_membership(
+    db: &impl ConnectionTrait,
+    user: &CurrentUser,
+    id: i64,
+) -> AppResult<(WorkspacesModel, bool)> {
+    if matches!(user.workspace_id, Some(workspace_id) if workspace_id != id) {
+        return Err(ServiceError::WorkspaceNotFoundError(id.to_string()));
+    }
+
+    let res = WorkspaceMembersEntity::find_by_id((id, user.id))
+        .find_also_related(WorkspacesEntity)
+        .one(db)
+        .await?;
+    match res {
+        Some((member, Some(workspace))) => Ok((workspace, member.admin)),
+        _ => Err(ServiceError::WorkspaceNotFoundError(id.to_string())),
+    }
+}
+
+// A workspace is never left without anyone to manage its members
+async fn check_other_admins(db: &impl ConnectionTrait, id: i64, user_id: i64) -> AppResult<()> {
+    let admins = WorkspaceMembersEntity::find()
+        .filter(WorkspaceMembersColumn::WorkspaceId.eq(id))
+        .filter(WorkspaceMembersColumn::UserId.ne(user_id))
+        .filter(WorkspaceMembersColumn::Admin.eq(true))
+        .count(db)
+        .await?;
+
+    match admins {
+        0 => Err(ServiceError::LastWorkspaceAdminError(id)),
+        _ => Ok(()),
+    }
+}
@@ -1,8 +1,11 @@
+mod auth;
+mod blob;
 mod config;
 mod dto;
 mod error;
 mod handler;
 mod log;
+mod rate_limit;
 mod router;
 mod server;
 
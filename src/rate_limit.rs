@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::{HeaderName, HeaderValue, StatusCode};
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::CurrentUser,
+    config::{BucketConfig, RateLimitConfig},
+    error::{AppResult, ServiceError},
+};
+
+// Headers of draft-ietf-httpapi-ratelimit-headers telling clients how much they have left
+pub const RATE_LIMIT_LIMIT_HEADER: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATE_LIMIT_REMAINING_HEADER: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATE_LIMIT_RESET_HEADER: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+// Buckets of clients not seen for long are full again and are dropped once there are this many
+const MIN_PRUNE_AT: usize = 1024;
+
+// What is left in the bucket of a client after a request
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    /// seconds until the bucket is full again, or until the next request passes once it is empty
+    pub reset: u64,
+}
+
+impl RateLimit {
+    pub fn headers(&self) -> [(HeaderName, HeaderValue); 3] {
+        [
+            (RATE_LIMIT_LIMIT_HEADER, HeaderValue::from(self.limit)),
+            (
+                RATE_LIMIT_REMAINING_HEADER,
+                HeaderValue::from(self.remaining),
+            ),
+            (RATE_LIMIT_RESET_HEADER, HeaderValue::from(self.reset)),
+        ]
+    }
+}
+
+// Token buckets of the clients of one route group
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: BucketConfig,
+    trust_forwarded_for: bool,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+struct Buckets {
+    clients: HashMap<String, Bucket>,
+    prune_at: usize,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    // None when rate limiting is turned off
+    pub fn new(config: &RateLimitConfig, group: &str) -> Option<Self> {
+        config.enabled.then(|| Self {
+            config: config.bucket(group).clone(),
+            trust_forwarded_for: config.trust_forwarded_for,
+            buckets: Arc::new(Mutex::new(Buckets {
+                clients: HashMap::new(),
+                prune_at: MIN_PRUNE_AT,
+            })),
+        })
+    }
+
+    // Takes a token from the bucket of the client, which starts out full
+    async fn acquire(&self, client: String) -> Result<RateLimit, RateLimit> {
+        let mut buckets = self.buckets.lock().await;
+        let bucket = self.bucket(&mut buckets, client);
+        if bucket.tokens < 1.0 {
+            return Err(self.exhausted(bucket.tokens));
+        }
+        bucket.tokens -= 1.0;
+        Ok(self.left(bucket.tokens))
+    }
+
+    // Takes a token from the bucket of the client even when there is none left
+    async fn charge(&self, client: String) -> RateLimit {
+        let mut buckets = self.buckets.lock().await;
+        let bucket = self.bucket(&mut buckets, client);
+        bucket.tokens -= 1.0;
+        self.left(bucket.tokens)
+    }
+
+    // The refilled bucket of the client
+    fn bucket<'a>(&self, buckets: &'a mut Buckets, client: String) -> &'a mut Bucket {
+        let capacity = f64::from(self.config.capacity);
+        let now = Instant::now();
+        if buckets.clients.len() >= buckets.prune_at {
+            // a full bucket is the same as none at all
+            buckets
+                .clients
+                .retain(|_, bucket| self.refilled(bucket, now) < capacity);
+            buckets.prune_at = MIN_PRUNE_AT.max(buckets.clients.len() * 2);
+        }
+
+        let bucket = buckets.clients.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        bucket.tokens = self.refilled(bucket, now);
+        bucket.updated_at = now;
+        bucket
+    }
+
+    // Whether the client has a token left, without taking it
+    async fn check(&self, client: &str) -> Result<(), RateLimit> {
+        let buckets = self.buckets.lock().await;
+        match buckets.clients.get(client) {
+            Some(bucket) => {
+                let tokens = self.refilled(bucket, Instant::now());
+                if tokens < 1.0 {
+                    return Err(self.exhausted(tokens));
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn refilled(&self, bucket: &Bucket, now: Instant) -> f64 {
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        (bucket.tokens + elapsed * self.config.refill_per_second)
+            .min(f64::from(self.config.capacity))
+    }
+
+    fn left(&self, tokens: f64) -> RateLimit {
+        RateLimit {
+            limit: self.config.capacity,
+            // below zero after concurrent charges, the cast saturates
+            remaining: tokens as u32,
+            reset: self.seconds(f64::from(self.config.capacity) - tokens),
+        }
+    }
+
+    fn exhausted(&self, tokens: f64) -> RateLimit {
+        RateLimit {
+            limit: self.config.capacity,
+            remaining: 0,
+            reset: self.seconds(1.0 - tokens),
+        }
+    }
+
+    // Seconds until the bucket regains `tokens`, a rate of zero never refills, the cast saturates
+    fn seconds(&self, tokens: f64) -> u64 {
+        (tokens / self.config.refill_per_second).ceil() as u64
+    }
+
+    // Requests with an api key share its bucket, the ones of a user with an access token share
+    // theirs, everything else is told apart by the address of the client
+    fn client(&self, request: &Request) -> String {
+        match request.extensions().get::<CurrentUser>() {
+            Some(CurrentUser {
+                api_key_id: Some(api_key_id),
+                ..
+            }) => format!("api_key:{api_key_id}"),
+            Some(user) => format!("user:{}", user.id),
+            None => format!("address:{}", self.client_address(request)),
+        }
+    }
+
+    fn client_address(&self, request: &Request) -> String {
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| {
+                request
+                    .headers()
+                    .get_all("x-forwarded-for")
+                    .iter()
+                    .next_back()
+            })
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .map(str::trim)
+            .filter(|address| !address.is_empty());
+
+        match forwarded {
+            Some(address) => address.to_owned(),
+            None => request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// Lets a request through while its client has tokens left in the bucket of the route group.
+// Runs after `authenticate` where there is one, so that clients are told apart by credentials,
+// requests rejected there are limited by `rate_limit_unauthorized` instead.
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> AppResult<Response> {
+    let client = limiter.client(&request);
+    let limit = limiter
+        .acquire(client)
+        .await
+        .map_err(ServiceError::RateLimitedError)?;
+
+    let mut res = next.run(request).await;
+    res.headers_mut().extend(limit.headers());
+    Ok(res)
+}
+
+// Lets a request through while its address has tokens left, only requests rejected as
+// unauthorized use them up. Runs before `authenticate`, so that guessing tokens or api keys is
+// slowed down without limiting clients that have valid credentials. Concurrent guesses may take
+// the bucket a few tokens below zero, which only delays the next ones further.
+pub async fn rate_limit_unauthorized(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> AppResult<Response> {
+    let client = format!("address:{}", limiter.client_address(&request));
+    limiter
+        .check(&client)
+        .await
+        .map_err(ServiceError::RateLimitedError)?;
+
+    let mut res = next.run(request).await;
+    if res.status() == StatusCode::UNAUTHORIZED {
+        let limit = limiter.charge(client).await;
+        res.headers_mut().extend(limit.headers());
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::Body;
+
+    use super::*;
+
+    fn limiter(capacity: u32, refill_per_second: f64) -> RateLimiter {
+        let config = RateLimitConfig {
+            enabled: true,
+            trust_forwarded_for: false,
+            default: BucketConfig {
+                capacity,
+                refill_per_second,
+            },
+            groups: HashMap::new(),
+        };
+        RateLimiter::new(&config, "todos").unwrap()
+    }
+
+    #[test]
+    fn disabled_limiting_has_no_limiter() {
+        let config = RateLimitConfig {
+            enabled: false,
+            trust_forwarded_for: false,
+            default: BucketConfig {
+                capacity: 1,
+                refill_per_second: 1.0,
+            },
+            groups: HashMap::new(),
+        };
+
+        assert!(RateLimiter::new(&config, "todos").is_none());
+    }
+
+    #[tokio::test]
+    async fn buckets_start_full_and_run_empty() {
+        let limiter = limiter(3, 0.0);
+
+        for remaining in [2, 1, 0] {
+            let limit = limiter.acquire("a".to_owned()).await.unwrap();
+            assert_eq!(limit.limit, 3);
+            assert_eq!(limit.remaining, remaining);
+        }
+        let limit = limiter.acquire("a".to_owned()).await.unwrap_err();
+        assert_eq!(limit.remaining, 0);
+        // never refilled
+        assert_eq!(limit.reset, u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn clients_have_buckets_of_their_own() {
+        let limiter = limiter(1, 0.0);
+
+        assert!(limiter.acquire("a".to_owned()).await.is_ok());
+        assert!(limiter.acquire("a".to_owned()).await.is_err());
+        assert!(limiter.acquire("b".to_owned()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn buckets_refill_over_time() {
+        let limiter = limiter(1, 100.0);
+
+        assert!(limiter.acquire("a".to_owned()).await.is_ok());
+        assert_eq!(limiter.acquire("a".to_owned()).await.unwrap_err().reset, 1);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(limiter.acquire("a".to_owned()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn checks_take_nothing_and_charges_overdraw() {
+        let limiter = limiter(2, 0.0);
+
+        for _ in 0..5 {
+            assert!(limiter.check("a").await.is_ok());
+        }
+        limiter.charge("a".to_owned()).await;
+        assert!(limiter.check("a").await.is_ok());
+        let limit = limiter.charge("a".to_owned()).await;
+        assert_eq!(limit.remaining, 0);
+        assert!(limiter.check("a").await.is_err());
+
+        // concurrent failures may take the bucket below zero
+        let limit = limiter.charge("a".to_owned()).await;
+        assert_eq!(limit.remaining, 0);
+        assert!(limiter.acquire("a".to_owned()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn full_buckets_are_pruned() {
+        let limiter = limiter(1, 1e9);
+
+        for client in 0..MIN_PRUNE_AT {
+            limiter.acquire(client.to_string()).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        limiter.acquire("last".to_owned()).await.unwrap();
+
+        assert_eq!(limiter.buckets.lock().await.clients.len(), 1);
+    }
+
+    #[test]
+    fn clients_are_told_apart_by_credentials_then_address() {
+        let limiter = limiter(1, 1.0);
+        let user = |api_key_id| CurrentUser {
+            id: 5,
+            email: "alice@example.com".to_owned(),
+            workspace_id: None,
+            api_key_id,
+        };
+        let request = |user: Option<CurrentUser>| {
+            let mut request = Request::new(Body::empty());
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([192, 0, 2, 1], 4000))));
+            if let Some(user) = user {
+                request.extensions_mut().insert(user);
+            }
+            request
+        };
+
+        assert_eq!(limiter.client(&request(Some(user(Some(3))))), "api_key:3");
+        assert_eq!(limiter.client(&request(Some(user(None)))), "user:5");
+        assert_eq!(limiter.client(&request(None)), "address:192.0.2.1");
+    }
+
+    #[test]
+    fn forwarded_addresses_are_only_trusted_when_configured() {
+        let mut limiter = limiter(1, 1.0);
+        let mut request = Request::new(Body::empty());
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        request.headers_mut().insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("198.51.100.7, 203.0.113.9"),
+        );
+
+        assert_eq!(limiter.client_address(&request), "10.0.0.1");
+        limiter.trust_forwarded_for = true;
+        // only the entry appended by the proxy can be trusted
+        assert_eq!(limiter.client_address(&request), "203.0.113.9");
+    }
+}
@@ -0,0 +1,15 @@
+use axum::routing::get;
+
+use crate::{handler::api_keys, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/api-keys",
+            get(api_keys::get_api_keys).post(api_keys::post_api_keys),
+        )
+        .route(
+            "/v1/api-keys/:id",
+            get(api_keys::get_api_key_by_id).delete(api_keys::delete_api_key_by_id),
+        )
+}
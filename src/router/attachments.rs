@@ -0,0 +1,23 @@
+use axum::{extract::DefaultBodyLimit, handler::Handler, routing::get};
+
+use crate::{handler::attachments, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/todos/:id/attachments",
+            get(attachments::get_todo_attachments).post(
+                // uploads are streamed and bounded by the configured max size instead
+                attachments::post_todo_attachments.layer(DefaultBodyLimit::disable()),
+            ),
+        )
+        .route(
+            "/v1/todos/:id/attachments/:attachment_id",
+            get(attachments::get_todo_attachment_by_id)
+                .delete(attachments::delete_todo_attachment_by_id),
+        )
+        .route(
+            "/v1/todos/:id/attachments/:attachment_id/content",
+            get(attachments::get_todo_attachment_content),
+        )
+}
@@ -0,0 +1,11 @@
+use axum::routing::post;
+
+use crate::{handler::auth, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route("/v1/auth/register", post(auth::register))
+        .route("/v1/auth/login", post(auth::login))
+        .route("/v1/auth/refresh", post(auth::refresh))
+        .route("/v1/auth/revoke", post(auth::revoke))
+}
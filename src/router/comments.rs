@@ -0,0 +1,17 @@
+use axum::routing::get;
+
+use crate::{handler::comments, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/todos/:id/comments",
+            get(comments::get_todo_comments).post(comments::post_todo_comments),
+        )
+        .route(
+            "/v1/todos/:id/comments/:comment_id",
+            get(comments::get_todo_comment_by_id)
+                .put(comments::put_todo_comment_by_id)
+                .delete(comments::delete_todo_comment_by_id),
+        )
+}
@@ -0,0 +1,18 @@
+use axum::routing::get;
+
+use crate::{handler::lists, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route("/v1/lists", get(lists::get_lists).post(lists::post_lists))
+        .route(
+            "/v1/lists/:list_id",
+            get(lists::get_list_by_id)
+                .put(lists::put_list_by_id)
+                .delete(lists::delete_list_by_id),
+        )
+        .route(
+            "/v1/lists/:list_id/todos",
+            get(lists::get_list_todos).post(lists::post_list_todos),
+        )
+}
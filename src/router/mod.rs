@@ -1,12 +1,30 @@
+mod api_keys;
+mod attachments;
+mod auth;
+mod comments;
+mod lists;
+mod oidc;
 mod server;
+mod shares;
+mod tags;
 mod todos;
+mod trash;
+mod workspaces;
 
-use crate::{handler::openapi::ApiDoc, server::AppState};
-use axum::Router;
+use crate::{
+    handler::openapi::ApiDoc,
+    rate_limit::{rate_limit, rate_limit_unauthorized, RateLimiter},
+    server::AppState,
+};
+use axum::{middleware, Router};
 use http::header;
 use tower_http::{
-    compression::CompressionLayer, cors::CorsLayer, decompression::DecompressionLayer,
-    propagate_header::PropagateHeaderLayer, trace,
+    compression::CompressionLayer,
+    cors::CorsLayer,
+    decompression::DecompressionLayer,
+    propagate_header::PropagateHeaderLayer,
+    request_id::{MakeRequestUuid, SetRequestIdLayer},
+    trace,
 };
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -16,8 +34,36 @@ pub fn init(state: AppState) -> Router {
         .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()));
     let router = server::add_routers(router);
 
-    let api_router = Router::new();
-    let api_router = todos::add_routers(api_router);
+    let api_router = Router::new()
+        .merge(rate_limited(&state, "todos", todos::add_routers))
+        .merge(rate_limited(&state, "trash", trash::add_routers))
+        .merge(rate_limited(&state, "tags", tags::add_routers))
+        .merge(rate_limited(&state, "lists", lists::add_routers))
+        .merge(rate_limited(&state, "comments", comments::add_routers))
+        .merge(rate_limited(
+            &state,
+            "attachments",
+            attachments::add_routers,
+        ))
+        .merge(rate_limited(&state, "shares", shares::add_routers))
+        .merge(rate_limited(&state, "api_keys", api_keys::add_routers))
+        .merge(rate_limited(&state, "workspaces", workspaces::add_routers));
+    // every route added above requires an access token or api key
+    let api_router = api_router.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        crate::auth::authenticate,
+    ));
+    // outside `authenticate`, where requests without valid credentials have not been rejected yet
+    let api_router = match RateLimiter::new(&state.config.rate_limit, "unauthorized") {
+        Some(limiter) => api_router.route_layer(middleware::from_fn_with_state(
+            limiter,
+            rate_limit_unauthorized,
+        )),
+        None => api_router,
+    };
+    let api_router = api_router
+        .merge(rate_limited(&state, "auth", auth::add_routers))
+        .merge(rate_limited(&state, "oidc", oidc::add_routers));
     let router = router.nest("/api", api_router);
 
     router
@@ -34,6 +80,21 @@ pub fn init(state: AppState) -> Router {
         .layer(PropagateHeaderLayer::new(header::HeaderName::from_static(
             "x-request-id",
         )))
+        // generated unless the client sent one, recorded in the history of todos
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         // TODO be more restrictive
         .layer(CorsLayer::permissive())
 }
+
+// Route groups get buckets of their own, so that hammering one leaves the others usable
+fn rate_limited(
+    state: &AppState,
+    group: &str,
+    add_routers: fn(Router<AppState>) -> Router<AppState>,
+) -> Router<AppState> {
+    let router = add_routers(Router::new());
+    match RateLimiter::new(&state.config.rate_limit, group) {
+        Some(limiter) => router.route_layer(middleware::from_fn_with_state(limiter, rate_limit)),
+        None => router,
+    }
+}
@@ -0,0 +1,9 @@
+use axum::routing::get;
+
+use crate::{handler::oidc, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route("/v1/auth/oidc/authorize", get(oidc::authorize))
+        .route("/v1/auth/oidc/callback", get(oidc::callback))
+}
@@ -0,0 +1,15 @@
+use axum::routing::get;
+
+use crate::{handler::shares, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/shares",
+            get(shares::get_shares).post(shares::post_shares),
+        )
+        .route(
+            "/v1/shares/:id",
+            get(shares::get_share_by_id).delete(shares::delete_share_by_id),
+        )
+}
@@ -0,0 +1,14 @@
+use axum::routing::get;
+
+use crate::{handler::tags, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route("/v1/tags", get(tags::get_tags).post(tags::post_tags))
+        .route(
+            "/v1/tags/:id",
+            get(tags::get_tag_by_id)
+                .put(tags::put_tag_by_id)
+                .delete(tags::delete_tag_by_id),
+        )
+}
@@ -1,14 +1,30 @@
-use axum::routing::get;
+use axum::routing::{get, post};
 
-use crate::{handler::todos, server::AppState};
+use crate::{
+    handler::{history, recurrence, subtasks, todos},
+    server::AppState,
+};
 
 pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
     router
         .route("/v1/todos", get(todos::get_todos).post(todos::post_todos))
+        .route("/v1/todos/search", get(todos::search_todos))
+        // matches any suffix of `/v1/todos`, the handler dispatches on it
+        .route("/v1/todos:method", post(todos::post_todos_custom_method))
         .route(
             "/v1/todos/:id",
             get(todos::get_todo_by_id)
                 .put(todos::put_todo_by_id)
-                .delete(todos::delete_todo_by_id),
+                .patch(todos::patch_todo_by_id)
+                .delete(todos::delete_todo_by_id)
+                // `{id}:{method}` custom methods, the handler dispatches on the method
+                .post(todos::post_todo_custom_method),
+        )
+        .route("/v1/todos/:id/children", get(subtasks::get_todo_children))
+        .route("/v1/todos/:id/subtree", get(subtasks::get_todo_subtree))
+        .route("/v1/todos/:id/history", get(history::get_todo_history))
+        .route(
+            "/v1/todos/:id/occurrences",
+            get(recurrence::get_todo_occurrences),
         )
 }
@@ -0,0 +1,12 @@
+use axum::routing::{delete, get};
+
+use crate::{handler::trash, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/trash",
+            get(trash::get_trash).delete(trash::purge_trash),
+        )
+        .route("/v1/trash/:id", delete(trash::purge_trashed_todo_by_id))
+}
@@ -0,0 +1,20 @@
+use axum::routing::{delete, get};
+
+use crate::{handler::workspaces, server::AppState};
+
+pub fn add_routers(router: axum::Router<AppState>) -> axum::Router<AppState> {
+    router
+        .route(
+            "/v1/workspaces",
+            get(workspaces::get_workspaces).post(workspaces::post_workspaces),
+        )
+        .route("/v1/workspaces/:id", get(workspaces::get_workspace_by_id))
+        .route(
+            "/v1/workspaces/:id/members",
+            get(workspaces::get_workspace_members).post(workspaces::post_workspace_members),
+        )
+        .route(
+            "/v1/workspaces/:id/members/:user_id",
+            delete(workspaces::delete_workspace_member),
+        )
+}
@@ -1,3 +1,5 @@
+use std::net::SocketAddr;
+
 use anyhow::{Context, Result};
 use migration::{Migrator, MigratorTrait};
 use tokio_graceful_shutdown::SubsystemHandle;
@@ -50,9 +52,14 @@ impl AppServer {
         let addr = self.listener.local_addr()?;
         info!("listening on {}", addr);
 
-        axum::serve(self.listener, self.router)
-            .await
-            .context(format!("cannot start axum service at {}", addr))?;
+        // the address of clients tells apart the ones without credentials when rate limiting
+        axum::serve(
+            self.listener,
+            self.router
+                .into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .context(format!("cannot start axum service at {}", addr))?;
         Ok(())
     }
 }
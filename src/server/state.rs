@@ -1,4 +1,8 @@
-use crate::config::AppConfig;
+use crate::{
+    auth::{JwtKeys, OidcClient},
+    blob::BlobStore,
+    config::AppConfig,
+};
 use anyhow::{Ok, Result};
 use sea_orm::Database;
 use tracing::info;
@@ -9,6 +13,9 @@ use std::sync::Arc;
 pub struct AppState {
     pub config: Arc<crate::config::AppConfig>,
     pub database: Arc<sea_orm::DatabaseConnection>,
+    pub blobs: Arc<dyn BlobStore>,
+    pub jwt: Arc<JwtKeys>,
+    pub oidc: Option<Arc<OidcClient>>,
 }
 
 impl AppState {
@@ -16,9 +23,22 @@ impl AppState {
         info!("connecting to database");
         let database = Arc::new(Database::connect(&config.database.uri).await?);
 
+        info!("opening attachment store");
+        let blobs = crate::blob::new(&config.attachments.store).await?;
+
+        info!("loading token signing keys");
+        let jwt = Arc::new(JwtKeys::new(&config.auth)?);
+        let oidc = match &config.auth.oidc {
+            Some(oidc) => Some(Arc::new(OidcClient::new(oidc)?)),
+            None => None,
+        };
+
         Ok(Self {
             config: Arc::new(config),
             database,
+            blobs,
+            jwt,
+            oidc,
         })
     }
 }